@@ -19,6 +19,9 @@ macro_rules! timed {
 
         println!("{}", res);
         println!("Took: {:.2}s", (elapsed as f64) / 1000f64);
+        if let Some(stats) = ::aoc_utils::search::take_stats() {
+            println!("\t\tSearch: {}", stats);
+        }
 
     };
 }
@@ -1,37 +1,32 @@
 use crate::aoc_test;
-use md5;
+use aoc_utils::miner::{leading_zeros, Digest, Miner};
 
 pub fn parse(input: &str) -> &str {
     input
 }
 
-fn is_valid(input: &str, i: usize, difficulty: usize) -> bool {
-    let st = input.to_string() + &i.to_string();
-    let digest = md5::compute(st.as_bytes());
-    let bytes = difficulty / 2;
-
-    for i in 0..bytes {
-        if digest[i] != 0 {
-            return false
-        }
-    }
-    if (difficulty % 2 == 1) && (digest[bytes] & 0xF0) != 0 {
-        return false
-    }
-    true
+fn is_valid(digest: &Digest, difficulty: usize) -> bool {
+    leading_zeros(digest) >= difficulty
+}
+
+fn mine(input: &str, difficulty: usize) -> usize {
+    Miner::new(input)
+        .find(0, |d| is_valid(d, difficulty))
+        .unwrap()
+        .0
 }
 
 pub fn part1(input: &str) -> usize {
-    (0..).into_iter().find(|i| is_valid(input, *i, 5)).unwrap()
+    mine(input, 5)
 }
 
 pub fn part2(input: &str) -> usize {
-    (0..).into_iter().find(|i| is_valid(input, *i, 6)).unwrap()
+    mine(input, 6)
 }
 
 #[test]
 fn test_valid(){
-    assert!(is_valid("abcdef", 609043, 5));
+    assert!(is_valid(&Miner::new("abcdef").hash(609043), 5));
 
 }
 
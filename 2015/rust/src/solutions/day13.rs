@@ -1,6 +1,8 @@
+use aoc_utils::graph::Interner;
+use aoc_utils::tsp::{Objective, Shape, Tsp};
 use lazy_static::lazy_static;
 use regex::Regex;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 
 lazy_static! {
     static ref RE: Regex = Regex::new(r"(?P<who>\w+) would (?P<sign>lose|gain) (?P<amount>\d+) happiness units by sitting next to (?P<other>\w+)").unwrap();
@@ -27,41 +29,24 @@ pub fn parse(input: &str) -> HashMap<(&str, &str), isize> {
         .collect()
 }
 
+/// Everyone sits in a circle, so the happiest arrangement is the longest cycle through the
+/// table, where each pair of neighbours adds the happiness of both
 pub fn part1(rules: &HashMap<(&str, &str), isize>) -> isize {
-    let mut people: HashSet<&str> = HashSet::new();
-    people.extend(rules.iter().map(|((a, _), _)| a));
-    people.extend(rules.iter().map(|((_, b), _)| b));
-
-    let mut configs: Vec<Vec<&str>> = people.iter().map(|a| vec![*a]).collect();
-
-    let mut finished: Vec<_> = vec![];
-    while let Some(cfg) = configs.pop() {
-        if cfg.len() == people.len() {
-            finished.push(cfg);
-            continue;
-        }
-        for i in &people {
-            if !cfg.contains(i) {
-                let mut c = cfg.clone();
-                c.push(i);
-                configs.push(c);
-            }
+    let mut people = Interner::new();
+    for &(a, b) in rules.keys() {
+        people.intern(a);
+        people.intern(b);
+    }
+    let happiness = |a, b| *rules.get(&(a, b)).unwrap_or(&0) as i64;
+    let mut table = Tsp::new(people.len());
+    for a in 0..people.len() {
+        for b in 0..a {
+            let (x, y) = (*people.label(a), *people.label(b));
+            table.add_edge(a, b, happiness(x, y) + happiness(y, x));
         }
     }
-    finished
-        .iter()
-        .map(|pp| {
-            let mut score = 0;
-            for ix in 0..pp.len() {
-                score += rules
-                    .get(&(pp[ix], pp[(ix + pp.len() - 1) % pp.len()]))
-                    .unwrap_or(&0);
-                score += rules.get(&(pp[ix], pp[(ix + 1) % pp.len()])).unwrap_or(&0);
-            }
-            score
-        })
-        .max()
-        .unwrap()
+    let seating = table.solve(Objective::Max, Shape::Cycle, None).unwrap();
+    seating.cost as isize
 }
 
 pub fn part2(rules: &HashMap<(&str, &str), isize>) -> isize {
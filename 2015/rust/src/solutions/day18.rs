@@ -1,88 +1,64 @@
-use std::cmp::min;
+use aoc_utils::automaton::{life_like, moore, Bits, Dense, Edges};
 
-type Grid = Vec<Vec<usize>>;
-type GridRef<'a> = &'a [Vec<usize>];
+type Grid = Bits<2>;
 
-fn print_grid(grid: GridRef) {
+fn print_grid(grid: &Grid) {
     println!();
-    for row in grid {
+    for row in grid.rows() {
         for c in row {
-            match c {
-                0 => print!("."),
-                1 => print!("#"),
-                _ => panic!("unknown char"),
-            }
+            print!("{}", if c { '#' } else { '.' });
         }
         println!();
     }
 }
-fn change_stuck(grid: &mut Grid) {
-    for y in [0, grid.len() - 1] {
-        for x in [0, grid[y].len() - 1] {
-            grid[y][x] = 1;
-        }
-    }
-}
 
-fn mutate(grid: GridRef) -> Grid {
-    let mut next = grid.to_owned();
-    for j in 0..grid.len() {
-        for i in 0..grid[j].len() {
-            let ys = (j.saturating_sub(1), min(grid.len() - 1, j + 1));
-            let xs = (i.saturating_sub(1), min(grid[j].len() - 1, i + 1));
-            let around = (ys.0..=ys.1)
-                .flat_map(|y| {
-                    (xs.0..=xs.1)
-                        .filter(move |x| (*x, y) != (i, j))
-                        .map(move |x| grid[y][x])
-                })
-                .sum::<usize>();
-
-            match (grid[j][i], around) {
-                (1, a) if !(2..=3).contains(&a) => next[j][i] = 0,
-                (0, 3) => next[j][i] = 1,
-                _ => {} // DO nothing, value did not change
-            }
+fn change_stuck(grid: &mut Grid) {
+    let [height, width] = grid.dims().map(|d| d as isize);
+    for y in [0, height - 1] {
+        for x in [0, width - 1] {
+            grid.set([y, x], true);
         }
     }
-    next
 }
 
 pub fn parse(input: &str) -> Grid {
-    input
+    let rows = input
         .lines()
         .filter(|line| !line.is_empty())
         .map(|line| {
             line.chars()
                 .map(|c| match c {
-                    '#' => 1,
-                    '.' => 0,
+                    '#' => true,
+                    '.' => false,
                     _ => panic!("invalid character"),
                 })
                 .collect()
         })
-        .collect()
+        .collect();
+    Dense::from_rows(rows, false, Edges::Fixed)
 }
 
-pub fn part1(input: GridRef) -> usize {
+pub fn part1(input: &Grid) -> usize {
     evolve(input, 100, false)
 }
 
-pub fn evolve(input: GridRef, times: usize, stuck: bool) -> usize {
-    let mut input = input.to_owned();
-    stuck.then(|| change_stuck(&mut input));
+pub fn evolve(input: &Grid, times: usize, stuck: bool) -> usize {
+    let mut grid = input.clone();
+    let neighbors = moore();
+    let rule = life_like(&[3], &[2, 3]);
+    stuck.then(|| change_stuck(&mut grid));
     for step in 0..times {
         if cfg!(debug_assertions) {
             println!("Step: {}", step);
-            print_grid(&input);
+            print_grid(&grid);
         }
-        input = mutate(&input);
-        stuck.then(|| change_stuck(&mut input));
+        grid.step(&neighbors, &rule);
+        stuck.then(|| change_stuck(&mut grid));
     }
-    input.iter().flatten().sum::<usize>()
+    grid.count(true)
 }
 
-pub fn part2(input: GridRef) -> usize {
+pub fn part2(input: &Grid) -> usize {
     evolve(input, 100, true)
 }
 
@@ -97,4 +73,5 @@ fn test_example() {
 ####..",
     );
     assert_eq!(evolve(&input, 4, false), 4);
+    assert_eq!(evolve(&input, 5, true), 17);
 }
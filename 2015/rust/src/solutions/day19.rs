@@ -1,4 +1,5 @@
-use std::collections::{BinaryHeap, HashSet};
+use aoc_utils::grammar::{Grammar, Symbol};
+use std::collections::HashSet;
 type Molecule = String;
 type RefMolecule<'a> = &'a str;
 type Reaction = (String, String);
@@ -31,8 +32,41 @@ fn combinations(rules: &[Reaction], input: RefMolecule) -> HashSet<String> {
         .collect::<HashSet<String>>()
 }
 
-fn reverse_rules(rules: &[Reaction]) -> Vec<Reaction> {
-    rules.iter().map(|(a, b)| (b.clone(), a.clone())).collect()
+/// Split a molecule into its elements, e.g. `CRnAl` into `C`, `Rn` and `Al`
+fn elements(molecule: &str) -> Vec<&str> {
+    let mut starts: Vec<usize> = molecule
+        .char_indices()
+        .filter(|(_, c)| !c.is_lowercase())
+        .map(|(i, _)| i)
+        .collect();
+    if molecule.chars().all(|c| c.is_lowercase()) {
+        // A single element in lowercase, like `e`
+        starts = vec![0];
+    }
+    starts.push(molecule.len());
+    starts.windows(2).map(|w| &molecule[w[0]..w[1]]).collect()
+}
+
+/// Every replacement is a rule, and an element that can be replaced also matches itself (at no
+/// cost), so the fewest steps to make the molecule from `e` is the cheapest derivation
+fn grammar(rules: &[Reaction]) -> Grammar {
+    let replaceable: HashSet<&str> = rules.iter().map(|(from, _)| from.as_str()).collect();
+    let symbol = |element: &str| {
+        if replaceable.contains(element) {
+            Symbol::Rule(element.to_string())
+        } else {
+            Symbol::Terminal(element.to_string())
+        }
+    };
+    let mut grammar = Grammar::new();
+    for (from, to) in rules {
+        let rhs: Vec<Symbol> = elements(to).into_iter().map(symbol).collect();
+        grammar.add_rule(from, &rhs);
+    }
+    for element in &replaceable {
+        grammar.add_rule_with_cost(element, &[Symbol::Terminal(element.to_string())], 0);
+    }
+    grammar
 }
 
 pub fn part1((rules, target): &Input) -> usize {
@@ -40,33 +74,9 @@ pub fn part1((rules, target): &Input) -> usize {
 }
 
 pub fn part2((rules, target): &Input) -> usize {
-    let mut options: BinaryHeap<(usize, String)> = BinaryHeap::new();
-    let mut visited: HashSet<String> = HashSet::new();
-
-    let rules = &reverse_rules(rules);
-
-    let input = target;
-    options.push((0, input.clone()));
-
-    let target = String::from("e");
-
-    while let Some(opt) = options.pop() {
-        let i = opt.0 + 1;
-        let input = opt.1;
-
-        for c in combinations(rules, &input) {
-            if c == target {
-                return i;
-            }
-            if c.len() > input.len() {
-                continue;
-            }
-            if visited.insert(c.clone()) {
-                options.push((i, c));
-            }
-        }
-    }
-    unreachable!("could not find a solution");
+    grammar(rules)
+        .min_steps("e", &elements(target))
+        .expect("could not find a solution")
 }
 
 #[test]
@@ -1,6 +1,4 @@
-use std::collections::BinaryHeap;
-
-use std::cmp::Ordering;
+use aoc_utils::search::{memoized_dfs, Objective, Problem};
 
 #[derive(Debug, Clone, Default)]
 pub struct Game {
@@ -13,23 +11,50 @@ pub struct Game {
     hard: bool, // For part 2
 }
 
-impl PartialEq for Game {
-    fn eq(&self, other: &Self) -> bool {
-        self.mana_spent == other.mana_spent
+/// Fight that spends the least mana
+struct Fight;
+
+impl Problem for Fight {
+    type State = Game;
+    /// Boss hp, wizard hp and mana, effects, and whose turn it is
+    type Key = (usize, usize, usize, Vec<(Spell, usize)>, usize);
+
+    fn objective(&self) -> Objective {
+        Objective::Min
     }
-}
 
-impl Eq for Game {}
+    fn successors(&self, game: &Game) -> Vec<(Game, i64)> {
+        if game.finished() {
+            return vec![];
+        }
+        game.clone()
+            .turn()
+            .into_iter()
+            .map(|next| {
+                let cost = next.mana_spent - game.mana_spent;
+                (next, cost as i64)
+            })
+            .collect()
+    }
 
-impl Ord for Game {
-    fn cmp(&self, other: &Self) -> Ordering {
-        other.mana_spent.cmp(&self.mana_spent)
+    fn is_goal(&self, game: &Game) -> bool {
+        game.won()
     }
-}
 
-impl PartialOrd for Game {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+    fn key(&self, game: &Game) -> Self::Key {
+        let mut effects: Vec<(Spell, usize)> = game
+            .effects
+            .iter()
+            .map(|e| (e.spell.clone(), e.timeout))
+            .collect();
+        effects.sort();
+        (
+            game.boss.hp,
+            game.wizard.hp,
+            game.wizard.mana,
+            effects,
+            game.turns % 2,
+        )
     }
 }
 
@@ -180,7 +205,7 @@ struct Effect {
     timeout: usize,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 enum Spell {
     /// costs 53 mana. It instantly does 4 damage
     Missile,
@@ -247,32 +272,22 @@ pub fn parse(input: &str) -> Game {
     }
 }
 
-fn solve(wizard: Wizard, boss: Boss, hard: bool) -> Game {
-    let mut candidates: BinaryHeap<Game> = BinaryHeap::new();
-    candidates.push(Game {
+/// Least mana needed to win
+fn solve(wizard: Wizard, boss: Boss, hard: bool) -> usize {
+    let game = Game {
         boss,
         wizard,
         hard,
         ..Default::default()
-    });
-
-    while let Some(mut game) = candidates.pop() {
-        if game.won() {
-            debug_assert!(game.wizard.hp > 0);
-            return game;
-        }
-        if !game.finished() {
-            candidates.extend(game.turn().into_iter());
-        }
-    }
-    panic!("no solution found")
+    };
+    memoized_dfs(&Fight, game).value.expect("no solution found") as usize
 }
 
 pub fn part1(game: &Game) -> usize {
-    solve(game.wizard.clone(), game.boss.clone(), false).mana_spent
+    solve(game.wizard.clone(), game.boss.clone(), false)
 }
 pub fn part2(game: &Game) -> usize {
-    solve(game.wizard.clone(), game.boss.clone(), true).mana_spent
+    solve(game.wizard.clone(), game.boss.clone(), true)
 }
 
 #[cfg(test)]
@@ -281,19 +296,17 @@ mod test {
     #[test]
     fn test_example1() {
         let res = solve(Wizard::new(10, 250), Boss::new(13, 8), false);
-        dbg!(&res);
-        assert_eq!(res.mana_spent, 226);
+        assert_eq!(res, 226);
     }
     #[test]
     fn test_example2() {
         let res = solve(Wizard::new(10, 250), Boss::new(14, 8), false);
-        dbg!(&res);
         //Recharge 229
         //Shield
         //Drain
         //Poison
         //Magic Missile
 
-        assert_eq!(res.mana_spent, 229 + 113 + 73 + 173 + 53);
+        assert_eq!(res, 229 + 113 + 73 + 173 + 53);
     }
 }
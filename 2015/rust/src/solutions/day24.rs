@@ -1,62 +1,21 @@
-pub fn parse(input: &str) -> Vec<usize> {
-    let mut v = input.lines().flat_map(str::parse).collect::<Vec<usize>>();
-    v.sort();
-    v.reverse();
-    v
-}
+use aoc_utils::partition::best_group;
 
-fn valid_groups(weights: &[usize], target: usize) -> Vec<Vec<usize>> {
-    if target == 0 {
-        return vec![vec![]];
-    }
-    if weights.is_empty() {
-        return vec![];
-    }
-    let (&head, rest) = weights.split_first().unwrap();
-    let mut opts = valid_groups(rest, target);
-    if target >= head {
-        let mut others = valid_groups(rest, target - head);
-        for o in others.iter_mut() {
-            o.push(head);
-        }
-        opts.extend(others);
-    }
-    opts
+pub fn parse(input: &str) -> Vec<u64> {
+    input.lines().flat_map(str::parse).collect()
 }
 
-pub fn solve(weights: &[usize], n: usize) -> Option<usize> {
-    if n == 0 {
-        if weights.is_empty() {
-            return Some(0);
-        } else {
-            return None;
-        }
-    }
-    let target = weights.iter().sum::<usize>() / n;
-    let groups = valid_groups(weights, target);
-    let mut tups: Vec<_> = groups
-        .iter()
-        .map(|g| {
-            (
-                g.len(),
-                g.iter().fold(1usize, |a, b| a.saturating_mul(*b)),
-                g,
-            )
-        })
-        .collect();
-    tups.sort();
-    for (_, qe, g) in tups {
-        let missing: Vec<usize> = weights.iter().filter(|u| !g.contains(u)).copied().collect();
-        if solve(&missing, n - 1).is_some() {
-            return Some(qe);
-        }
-    }
-    None
+/// Quantum entanglement of the smallest first group (with the lowest entanglement among those)
+/// that leaves packages that can be split into the other `n - 1` groups
+pub fn solve(weights: &[u64], n: usize) -> Option<u64> {
+    let entanglement = |g: &[u64]| g.iter().fold(1u64, |a, b| a.saturating_mul(*b));
+    let group = best_group(weights, n, |g| (g.len(), entanglement(g)))?;
+    let group: Vec<u64> = group.iter().map(|&i| weights[i]).collect();
+    Some(entanglement(&group))
 }
 
-pub fn part1(weights: &[usize]) -> usize {
+pub fn part1(weights: &[u64]) -> u64 {
     solve(weights, 3).unwrap()
 }
-pub fn part2(weights: &[usize]) -> usize {
+pub fn part2(weights: &[u64]) -> u64 {
     solve(weights, 4).unwrap()
 }
@@ -1,6 +1,4 @@
-#![allow(clippy::ptr_arg)]
-
-use std::cmp::min;
+use aoc_utils::automaton::{moore, run_cycles, Dense, Edges};
 
 const OPEN: char = '.';
 const TREE: char = '|';
@@ -12,87 +10,39 @@ fn main() {
     println!("Solution 2: {}", solve1(&input, 1000000000));
 }
 
-type Grid = Vec<Vec<char>>;
-
-fn count(grid: &Grid, (i, j): (usize, usize), t: char) -> usize {
-    count_w(grid, (i, j), 3, t)
-}
-
-fn count_all(grid: &Grid, t: char) -> usize {
-    count_w(grid, (0, 0), usize::MAX, t)
-}
-fn count_w(grid: &Grid, (i, j): (usize, usize), size: usize, t: char) -> usize {
-    let w1 = size / 2;
-    let w2 = size - w1;
-    let range_i = i.saturating_sub(w1)..min(grid.len(), i + w2);
-    grid.get(range_i)
-        .into_iter()
-        .flatten()
-        .map(|row| row.get(j.saturating_sub(w1)..min(row.len(), j + w2)))
-        .flatten()
-        .flatten()
-        .filter(|tile| **tile == t)
-        .count()
-}
+type Grid = Dense<char, 2>;
 
 fn parse(input: &str) -> Grid {
-    input
+    let rows = input
         .lines()
-        .map(|line| line.trim().chars().collect())
-        .collect()
+        .map(|line| line.trim().chars().collect::<Vec<char>>())
+        .filter(|row| !row.is_empty())
+        .collect();
+    Dense::from_rows(rows, OPEN, Edges::Fixed)
+}
+
+fn step(grid: &mut Grid) {
+    grid.step(&moore(), |tile, neighbors| {
+        let count = |t| neighbors.iter().filter(|&&n| n == t).count();
+        match tile {
+            OPEN if count(TREE) >= 3 => TREE,
+            TREE if count(LUMBER) >= 3 => LUMBER,
+            LUMBER if count(LUMBER) < 1 || count(TREE) < 1 => OPEN,
+            OPEN | TREE | LUMBER => tile,
+            _ => panic!("unknown character"),
+        }
+    });
 }
 
 fn solve1(input: &str, minutes: usize) -> usize {
     let mut grid: Grid = parse(input);
-
-    let mut scores = vec![];
-    let mut freqs = vec![];
-
-    let mut missing = minutes;
-    while missing > 0 {
-        missing -= 1;
-        let mut ng = grid.clone();
-        for i in 0..ng.len() {
-            for j in 0..grid[i].len() {
-                match grid[i][j] {
-                    OPEN => {
-                        if count(&grid, (i, j), TREE) >= 3 {
-                            ng[i][j] = TREE;
-                        }
-                    }
-                    TREE => {
-                        if count(&grid, (i, j), LUMBER) >= 3 {
-                            ng[i][j] = LUMBER;
-                        }
-                    }
-                    LUMBER => {
-                        if count(&grid, (i, j), LUMBER) < 2 || count(&grid, (i, j), TREE) < 1 {
-                            ng[i][j] = OPEN;
-                        }
-                    }
-                    _ => {
-                        panic!("unknown character");
-                    }
-                }
-            }
-        }
-        grid = ng;
-        let score = count_all(&grid, TREE) * count_all(&grid, LUMBER);
-        let last = scores.iter().rev().position(|&x| x == score);
-        scores.push(score);
-        freqs.push(last);
-        if let Some(freq) = last {
-            if freqs.iter().rev().take(freq).all(|&x| x == Some(freq)) {
-                missing %= freq + 1;
-            }
-        }
-    }
-    *scores.last().unwrap()
+    run_cycles(&mut grid, minutes, step);
+    grid.count(TREE) * grid.count(LUMBER)
 }
 
 #[allow(dead_code)]
 fn print(grid: &Grid) {
-    for row in grid.iter() {
+    for row in grid.rows() {
         for c in row.iter() {
             print!("{}", c);
         }
@@ -116,26 +66,3 @@ fn test_example() {
 ...#.|..|.";
     assert_eq!(solve1(input, 10), 1147);
 }
-
-#[test]
-fn test_count() {
-    let example = "###";
-    let grid = &parse(example);
-    assert_eq!(count(grid, (0, 1), LUMBER), 3);
-}
-
-#[test]
-fn test_count1() {
-    let example = "111
-232
-343
-";
-    let grid = &parse(example);
-    assert_eq!(count(grid, (0, 0), '1'), 2);
-    assert_eq!(count(grid, (0, 0), '2'), 1);
-    assert_eq!(count(grid, (0, 0), '3'), 1);
-    assert_eq!(count(grid, (0, 1), '1'), 3);
-    assert_eq!(count(grid, (0, 1), '2'), 2);
-    assert_eq!(count(grid, (0, 1), '3'), 1);
-    assert_eq!(count(grid, (1, 1), '3'), 3);
-}
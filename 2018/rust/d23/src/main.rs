@@ -1,4 +1,6 @@
-use std::collections::{BinaryHeap, HashSet};
+use std::collections::BinaryHeap;
+
+use aoc_utils::cuboid::Cuboid;
 
 use itertools::Itertools;
 
@@ -19,10 +21,8 @@ type Coord = [isize; MAX_DIM];
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 struct BoundingBox {
-    min_coords: Coord,
-    max_coords: Coord,
+    region: Cuboid<MAX_DIM>,
     in_range: usize,
-    dims: [usize; MAX_DIM],
     dist_origin: usize,
 }
 
@@ -41,77 +41,29 @@ impl PartialOrd for BoundingBox {
 }
 
 impl BoundingBox {
-    fn new(min_coords: Coord, max_coords: Coord, bots: &[Bot]) -> Self {
-        let mut s = Self {
-            min_coords,
-            max_coords,
-            in_range: 0,
-            dist_origin: 0,
-            dims: [0, 0, 0],
-        };
-        s.dims = s
-            .max_coords
-            .iter()
-            .zip(s.min_coords.iter())
-            .map(|(a, b)| (a - b) as usize)
-            .collect::<Vec<_>>()
-            .try_into()
-            .unwrap();
-        s.dist_origin = s.distance([0, 0, 0]);
-        s.in_range = bots.iter().filter(|b| s.in_range(b)).count();
-        s
-    }
-    fn split(&self, bots: &[Bot]) -> Option<Vec<BoundingBox>> {
-        if self.dims.iter().sum::<usize>() == 0 {
-            return None;
-        }
-
-        let mut opts: HashSet<(Coord, Coord)> = HashSet::new();
-
-        let midp: Coord = self
-            .min_coords
-            .iter()
-            .zip(self.max_coords.iter())
-            .map(|(a, b)| (a + b) / 2)
-            .collect::<Vec<_>>()
-            .try_into()
-            .unwrap();
-
-        for i in 0..8 {
-            let mut minc = self.min_coords;
-            let mut maxc = midp;
-            for dim in 0..MAX_DIM {
-                if self.dims[dim] > 0 && i & (1 << dim) == (1 << dim) {
-                    minc[dim] = midp[dim] + 1;
-                    maxc[dim] = self.max_coords[dim];
-                }
-            }
-            opts.insert((minc, maxc));
+    fn new(region: Cuboid<MAX_DIM>, bots: &[Bot]) -> Self {
+        Self {
+            region,
+            in_range: bots
+                .iter()
+                .filter(|b| region.distance(&b.pos) as usize <= b.r)
+                .count(),
+            dist_origin: region.distance(&[0; MAX_DIM]) as usize,
         }
+    }
 
-        if opts.len() < 2 {
+    fn split(&self, bots: &[Bot]) -> Option<Vec<BoundingBox>> {
+        if self.region.dims().iter().all(|&d| d <= 1) {
             return None;
         }
-        let num_results = self
-            .dims
-            .iter()
-            .fold(1, |acc, d| if *d > 0 { acc * 2 } else { acc });
-        debug_assert_eq!(opts.len(), num_results);
         Some(
-            opts.into_iter()
-                .map(|(new_min, new_max)| BoundingBox::new(new_min, new_max, bots))
+            self.region
+                .split()
+                .into_iter()
+                .map(|region| BoundingBox::new(region, bots))
                 .collect(),
         )
     }
-
-    fn in_range(&self, bot: &Bot) -> bool {
-        dist(bot.pos, self.min_coords) + dist(bot.pos, self.max_coords)
-            <= (2 * bot.r + self.dims.iter().sum::<usize>())
-    }
-
-    fn distance(&self, pos: Coord) -> usize {
-        ((dist(self.min_coords, pos) + dist(self.max_coords, pos)) + 1) / 2
-    }
 }
 
 impl Bot {
@@ -163,7 +115,8 @@ fn solve2(input: &str) -> usize {
         })
         .unzip();
 
-    let b = BoundingBox::new(min.try_into().unwrap(), max.try_into().unwrap(), &bots);
+    let region = Cuboid::from_corners(min.try_into().unwrap(), max.try_into().unwrap());
+    let b = BoundingBox::new(region, &bots);
     debug_assert_eq!(b.in_range, bots.len());
 
     let mut boxes = BinaryHeap::from([b]);
@@ -192,10 +145,11 @@ fn main() {
 
 #[test]
 fn test_split() {
+    use std::collections::HashSet;
+
     let bots = &vec![];
-    let min_coords = [10, 12, 10];
-    let max_coords = [12, 14, 12];
-    let mut a = BoundingBox::new(min_coords, max_coords, bots);
+    let region = Cuboid::from_corners([10, 12, 10], [12, 14, 12]);
+    let a = BoundingBox::new(region, bots);
     let opts = a.split(bots);
     assert!(opts.is_some());
     let opts = opts.unwrap();
@@ -206,8 +160,8 @@ fn test_split() {
         if let Some(b) = boxes.pop() {
             if let Some(vs) = b.split(bots) {
                 for v in vs {
-                    visited.insert((v.min_coords, v.max_coords));
-                    if v.min_coords == v.max_coords && v.max_coords == [12, 12, 12] {
+                    visited.insert(v.region);
+                    if v.region == Cuboid::from_corners([12, 12, 12], [12, 12, 12]) {
                         break;
                     }
                     boxes.push(v);
@@ -219,21 +173,14 @@ fn test_split() {
         }
     }
 
-    let points_visited: HashSet<Vec<isize>> = visited
-        .iter()
-        .flat_map(|(min, max)| {
-            (0..MAX_DIM)
-                .into_iter()
-                .map(|i| min[i]..=max[i])
-                .multi_cartesian_product()
-        })
-        .collect();
-
-    let allpoints: HashSet<Vec<isize>> = (0..MAX_DIM)
-        .into_iter()
-        .map(|i| min_coords[i]..=max_coords[i])
-        .multi_cartesian_product()
-        .collect();
+    let points = |region: &Cuboid<MAX_DIM>| {
+        (0..MAX_DIM)
+            .map(|i| region.min[i]..region.max[i])
+            .multi_cartesian_product()
+            .collect::<Vec<_>>()
+    };
+    let points_visited: HashSet<Vec<isize>> = visited.iter().flat_map(points).collect();
+    let allpoints: HashSet<Vec<isize>> = points(&region).into_iter().collect();
     let not_visited: Vec<_> = allpoints.difference(&points_visited).collect();
     dbg!(&not_visited);
     assert!(not_visited.is_empty());
@@ -242,7 +189,7 @@ fn test_split() {
 #[test]
 fn test_range() {
     let bots = &parse(include_str!("../example2"));
-    let a = BoundingBox::new([12, 12, 12], [12, 12, 12], &bots);
+    let a = BoundingBox::new(Cuboid::from_corners([12, 12, 12], [12, 12, 12]), &bots);
     assert_eq!(a.in_range, 5);
 }
 
@@ -1,5 +1,5 @@
+use aoc_utils::unionfind::UnionFind;
 use std::cmp::{max,min};
-use std::collections::HashSet;
 
 type Coord = [isize; 4];
 
@@ -9,29 +9,15 @@ fn dist(p1: &Coord, p2: &Coord) -> usize {
 
 fn solve1(input: &str) -> usize {
     let coords = parse(input);
-    let mut group: Vec<usize> = (0..coords.len()).collect();
-    for ix in 0..coords.len()-1 {
-        let c1 = &coords[ix];
+    let mut constellations = UnionFind::new(coords.len());
+    for ix in 0..coords.len() {
         for jx in ix+1..coords.len() {
-            let c2 = &coords[jx];
-            if dist(c1, c2) <= 3 {
-                let g1 = group[ix];
-                let g2 = group[jx];
-                if g2 == jx {
-                    group[jx] = g1;
-                } else {
-                    for g in group.iter_mut(){
-                        if g == &g2 {
-                            *g = g1;
-                        }
-                    }
-                }
+            if dist(&coords[ix], &coords[jx]) <= 3 {
+                constellations.union(ix, jx);
             }
         }
     }
-
-    let uniq: HashSet<usize> = group.iter().copied().collect();
-    uniq.len()
+    constellations.components()
 }
 
 fn parse(input: &str) -> Vec<Coord> {
@@ -0,0 +1,586 @@
+//! Disassembler and decompiler for the elfcode used in days 19 and 21.
+//!
+//! The program is split into basic blocks by looking at every instruction that writes to the
+//! register bound with `#ip`. Jumps whose target can be computed statically become `goto`s, and
+//! loops are found with a dominator analysis over the resulting control flow graph.
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::str::FromStr;
+
+pub const REGISTERS: usize = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Opcode {
+    Addr,
+    Addi,
+    Mulr,
+    Muli,
+    Banr,
+    Bani,
+    Borr,
+    Bori,
+    Setr,
+    Seti,
+    Gtir,
+    Gtri,
+    Gtrr,
+    Eqir,
+    Eqri,
+    Eqrr,
+}
+
+impl Opcode {
+    /// Whether the `a` and `b` operands are registers (as opposed to immediates).
+    /// Operands that are ignored by the opcode are reported as immediates.
+    pub fn registers(self) -> (bool, bool) {
+        use Opcode::*;
+        match self {
+            Addr | Mulr | Banr | Borr | Gtrr | Eqrr => (true, true),
+            Addi | Muli | Bani | Bori | Gtri | Eqri | Setr => (true, false),
+            Gtir | Eqir => (false, true),
+            Seti => (false, false),
+        }
+    }
+
+    pub fn is_comparison(self) -> bool {
+        use Opcode::*;
+        matches!(self, Gtir | Gtri | Gtrr | Eqir | Eqri | Eqrr)
+    }
+
+    /// Apply the opcode to the already resolved values of its operands.
+    /// Returns `None` if the result overflows.
+    pub fn eval(self, a: isize, b: isize) -> Option<isize> {
+        use Opcode::*;
+        Some(match self {
+            Addr | Addi => a.checked_add(b)?,
+            Mulr | Muli => a.checked_mul(b)?,
+            Banr | Bani => a & b,
+            Borr | Bori => a | b,
+            Setr | Seti => a,
+            Gtir | Gtri | Gtrr => (a > b) as isize,
+            Eqir | Eqri | Eqrr => (a == b) as isize,
+        })
+    }
+
+    fn symbol(self) -> Option<&'static str> {
+        use Opcode::*;
+        match self {
+            Addr | Addi => Some("+"),
+            Mulr | Muli => Some("*"),
+            Banr | Bani => Some("&"),
+            Borr | Bori => Some("|"),
+            Gtir | Gtri | Gtrr => Some(">"),
+            Eqir | Eqri | Eqrr => Some("=="),
+            Setr | Seti => None,
+        }
+    }
+}
+
+impl FromStr for Opcode {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use Opcode::*;
+        Ok(match s {
+            "addr" => Addr,
+            "addi" => Addi,
+            "mulr" => Mulr,
+            "muli" => Muli,
+            "banr" => Banr,
+            "bani" => Bani,
+            "borr" => Borr,
+            "bori" => Bori,
+            "setr" => Setr,
+            "seti" => Seti,
+            "gtir" => Gtir,
+            "gtri" => Gtri,
+            "gtrr" => Gtrr,
+            "eqir" => Eqir,
+            "eqri" => Eqri,
+            "eqrr" => Eqrr,
+            _ => return Err(ParseError(format!("unknown opcode {s:?}"))),
+        })
+    }
+}
+
+impl fmt::Display for Opcode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = format!("{self:?}").to_lowercase();
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError(String);
+
+impl std::error::Error for ParseError {}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid elfcode: {}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Instruction {
+    pub op: Opcode,
+    pub a: isize,
+    pub b: isize,
+    pub c: usize,
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {} {}", self.op, self.a, self.b, self.c)
+    }
+}
+
+/// Where the execution can continue after an instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Target {
+    Line(usize),
+    Halt,
+}
+
+/// Control flow of a single instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    /// The instruction does not touch the IP, execution continues with the next one.
+    Next,
+    Goto(Target),
+    /// Relative jump through a register that holds the result of the previous comparison.
+    Branch {
+        flag: usize,
+        taken: Target,
+        not_taken: Target,
+    },
+    /// Jump to a target that depends on a register value not known statically.
+    Indirect,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub start: usize,
+    pub end: usize,
+    pub successors: Vec<Target>,
+    pub indirect: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Loop {
+    /// Index of the block that dominates the whole loop.
+    pub header: usize,
+    /// Indices of all the blocks in the loop, including the header.
+    pub body: BTreeSet<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    pub ip: Option<usize>,
+    pub code: Vec<Instruction>,
+}
+
+impl FromStr for Program {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut ip = None;
+        let mut code = vec![];
+        for line in input.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            if let Some(reg) = line.strip_prefix("#ip") {
+                let reg = reg
+                    .trim()
+                    .parse()
+                    .map_err(|_| ParseError(format!("invalid #ip line {line:?}")))?;
+                if reg >= REGISTERS {
+                    return Err(ParseError(format!("no register {reg}")));
+                }
+                ip = Some(reg);
+                continue;
+            }
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let [op, a, b, c] = parts[..] else {
+                return Err(ParseError(format!("expected 4 fields in {line:?}")));
+            };
+            let num = |s: &str| {
+                s.parse::<isize>()
+                    .map_err(|_| ParseError(format!("invalid operand {s:?} in {line:?}")))
+            };
+            let c = num(c)?;
+            if !(0..REGISTERS as isize).contains(&c) {
+                return Err(ParseError(format!("invalid output register in {line:?}")));
+            }
+            code.push(Instruction {
+                op: op.parse()?,
+                a: num(a)?,
+                b: num(b)?,
+                c: c as usize,
+            });
+        }
+        Ok(Program { ip, code })
+    }
+}
+
+impl Program {
+    fn target(&self, value: isize) -> Target {
+        match value.checked_add(1).map(usize::try_from) {
+            Some(Ok(line)) if line < self.code.len() => Target::Line(line),
+            _ => Target::Halt,
+        }
+    }
+
+    /// Statically compute the control flow of the instruction at `pc`.
+    ///
+    /// Reading the IP register always yields `pc`, so any jump that only depends on it and on
+    /// immediates is resolved. A register written by a comparison right before the jump is known
+    /// to be either 0 or 1, which turns `addr flag ip ip` into a conditional branch.
+    pub fn flow(&self, pc: usize) -> Flow {
+        let Some(ip) = self.ip else {
+            return Flow::Next;
+        };
+        let inst = self.code[pc];
+        if inst.c != ip {
+            return Flow::Next;
+        }
+        let flag = pc
+            .checked_sub(1)
+            .map(|prev| self.code[prev])
+            .filter(|prev| prev.op.is_comparison() && prev.c != ip)
+            .map(|prev| prev.c);
+
+        let (a_reg, b_reg) = inst.op.registers();
+        let value = |reg: bool, v: isize, flag_value: isize| match (reg, flag) {
+            (false, _) => Some(v),
+            (true, _) if v == ip as isize => Some(pc as isize),
+            (true, Some(f)) if v == f as isize => Some(flag_value),
+            _ => None,
+        };
+        let eval = |flag_value| {
+            let a = value(a_reg, inst.a, flag_value)?;
+            let b = value(b_reg, inst.b, flag_value)?;
+            Some(self.target(inst.op.eval(a, b)?))
+        };
+        let (Some(taken), Some(not_taken)) = (eval(1), eval(0)) else {
+            return Flow::Indirect;
+        };
+        match flag {
+            Some(flag) if taken != not_taken => Flow::Branch {
+                flag,
+                taken,
+                not_taken,
+            },
+            _ => Flow::Goto(taken),
+        }
+    }
+
+    /// Split the program into basic blocks, in program order.
+    pub fn blocks(&self) -> Vec<Block> {
+        let len = self.code.len();
+        let mut leaders = BTreeSet::from([0]);
+        for pc in 0..len {
+            let targets = match self.flow(pc) {
+                Flow::Next => continue,
+                Flow::Goto(t) => vec![t],
+                Flow::Branch {
+                    taken, not_taken, ..
+                } => vec![taken, not_taken],
+                Flow::Indirect => vec![],
+            };
+            leaders.insert(pc + 1);
+            leaders.extend(targets.into_iter().filter_map(|t| match t {
+                Target::Line(l) => Some(l),
+                Target::Halt => None,
+            }));
+        }
+        let leaders: Vec<usize> = leaders.into_iter().filter(|&l| l < len).collect();
+        leaders
+            .iter()
+            .enumerate()
+            .map(|(i, &start)| {
+                let end = leaders.get(i + 1).copied().unwrap_or(len);
+                let (successors, indirect) = match self.flow(end - 1) {
+                    Flow::Next => (vec![self.target(end as isize - 1)], false),
+                    Flow::Goto(t) => (vec![t], false),
+                    Flow::Branch {
+                        taken, not_taken, ..
+                    } => (vec![not_taken, taken], false),
+                    Flow::Indirect => (vec![], true),
+                };
+                Block {
+                    start,
+                    end,
+                    successors,
+                    indirect,
+                }
+            })
+            .collect()
+    }
+
+    /// Find the natural loops of the program, using the blocks returned by [`Program::blocks`].
+    pub fn loops(&self, blocks: &[Block]) -> Vec<Loop> {
+        let succs = control_graph(blocks);
+        let root = blocks.len();
+        let mut preds = vec![vec![]; succs.len()];
+        for (from, tos) in succs.iter().enumerate() {
+            for &to in tos {
+                preds[to].push(from);
+            }
+        }
+
+        let reachable = reachable(&succs, root);
+        let all: BTreeSet<usize> = reachable.iter().copied().collect();
+        let mut dom: Vec<BTreeSet<usize>> = (0..succs.len())
+            .map(|i| {
+                if i == root {
+                    BTreeSet::from([root])
+                } else {
+                    all.clone()
+                }
+            })
+            .collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &node in reachable.iter().skip(1) {
+                let mut new = preds[node]
+                    .iter()
+                    .filter(|p| all.contains(p))
+                    .map(|&p| dom[p].clone())
+                    .reduce(|acc, d| acc.intersection(&d).copied().collect())
+                    .unwrap_or_default();
+                new.insert(node);
+                if new != dom[node] {
+                    dom[node] = new;
+                    changed = true;
+                }
+            }
+        }
+
+        let mut loops: Vec<Loop> = vec![];
+        for &from in reachable.iter() {
+            for &header in succs[from].iter().filter(|h| dom[from].contains(h)) {
+                let mut body = BTreeSet::from([header, from]);
+                let mut stack = vec![from];
+                while let Some(node) = stack.pop() {
+                    if node == header {
+                        continue;
+                    }
+                    for &p in &preds[node] {
+                        if p != root && all.contains(&p) && body.insert(p) {
+                            stack.push(p);
+                        }
+                    }
+                }
+                match loops.iter_mut().find(|l| l.header == header) {
+                    Some(l) => l.body.extend(body),
+                    None => loops.push(Loop { header, body }),
+                }
+            }
+        }
+        loops.sort_by_key(|l| l.header);
+        loops
+    }
+
+    fn operand(&self, pc: usize, reg: bool, value: isize) -> String {
+        match (reg, self.ip) {
+            (false, _) => value.to_string(),
+            (true, Some(ip)) if value == ip as isize => pc.to_string(),
+            (true, _) => format!("r{value}"),
+        }
+    }
+
+    fn expression(&self, pc: usize) -> String {
+        let inst = self.code[pc];
+        let (a_reg, b_reg) = inst.op.registers();
+        let a = self.operand(pc, a_reg, inst.a);
+        match inst.op.symbol() {
+            None => a,
+            Some(sym) => format!("{a} {sym} {}", self.operand(pc, b_reg, inst.b)),
+        }
+    }
+
+    fn statement(&self, pc: usize) -> String {
+        let label = |t: Target| match t {
+            Target::Line(l) => format!("goto L{l}"),
+            Target::Halt => "halt".into(),
+        };
+        match self.flow(pc) {
+            Flow::Next => format!("r{} = {}", self.code[pc].c, self.expression(pc)),
+            Flow::Goto(t) => label(t),
+            Flow::Branch {
+                flag,
+                taken,
+                not_taken,
+            } => {
+                if not_taken == Target::Line(pc + 1) {
+                    format!("if r{flag} {}", label(taken))
+                } else {
+                    format!("if r{flag} {} else {}", label(taken), label(not_taken))
+                }
+            }
+            Flow::Indirect => format!("goto ({}) + 1", self.expression(pc)),
+        }
+    }
+
+    /// Render the program as pseudo-code, with one label per basic block.
+    pub fn decompile(&self) -> String {
+        let blocks = self.blocks();
+        let loops = self.loops(&blocks);
+        let reachable: BTreeSet<usize> = reachable(&control_graph(&blocks), blocks.len())
+            .into_iter()
+            .collect();
+
+        let mut out = String::new();
+        if let Some(ip) = self.ip {
+            out.push_str(&format!("// #ip {ip}: r{ip} is the instruction pointer\n"));
+        }
+        for (i, block) in blocks.iter().enumerate() {
+            out.push_str(&format!("L{}:", block.start));
+            let mut notes = vec![];
+            for l in loops.iter().filter(|l| l.header == i) {
+                let body: Vec<String> = l
+                    .body
+                    .iter()
+                    .map(|&b| format!("L{}", blocks[b].start))
+                    .collect();
+                notes.push(format!("loop over {}", body.join(", ")));
+            }
+            if !reachable.contains(&i) {
+                notes.push("unreachable".into());
+            }
+            if !notes.is_empty() {
+                out.push_str(&format!("  // {}", notes.join("; ")));
+            }
+            out.push('\n');
+            for pc in block.start..block.end {
+                out.push_str(&format!("  {pc:>3} | {}\n", self.statement(pc)));
+            }
+        }
+        out
+    }
+}
+
+/// Successors of every block, as indices into `blocks`, plus a virtual entry node at the end.
+///
+/// The entry node points to the first block. An indirect jump could land anywhere, so if there
+/// are any, every block without predecessors is considered an entry point as well.
+fn control_graph(blocks: &[Block]) -> Vec<Vec<usize>> {
+    let index: HashMap<usize, usize> = blocks
+        .iter()
+        .enumerate()
+        .map(|(i, b)| (b.start, i))
+        .collect();
+    let mut succs: Vec<Vec<usize>> = blocks
+        .iter()
+        .map(|b| {
+            b.successors
+                .iter()
+                .filter_map(|t| match t {
+                    Target::Line(l) => index.get(l).copied(),
+                    Target::Halt => None,
+                })
+                .collect()
+        })
+        .collect();
+    let mut entries = vec![];
+    if !blocks.is_empty() {
+        entries.push(0);
+    }
+    if blocks.iter().any(|b| b.indirect) {
+        let targets: BTreeSet<usize> = succs.iter().flatten().copied().collect();
+        entries.extend((1..blocks.len()).filter(|b| !targets.contains(b)));
+    }
+    succs.push(entries);
+    succs
+}
+
+/// Nodes reachable from `root`, in depth-first preorder.
+fn reachable(succs: &[Vec<usize>], root: usize) -> Vec<usize> {
+    let mut seen = vec![false; succs.len()];
+    let mut order = vec![];
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        if seen[node] {
+            continue;
+        }
+        seen[node] = true;
+        order.push(node);
+        stack.extend(succs[node].iter().rev().filter(|&&n| !seen[n]));
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "#ip 0
+seti 5 0 1
+seti 6 0 2
+addi 0 1 0
+addr 1 2 3
+setr 1 0 0
+seti 8 0 4
+seti 9 0 5";
+
+    #[test]
+    fn test_example_flow() {
+        let program: Program = EXAMPLE.parse().unwrap();
+        assert_eq!(program.flow(0), Flow::Next);
+        assert_eq!(program.flow(2), Flow::Goto(Target::Line(4)));
+        assert_eq!(program.flow(3), Flow::Next);
+        assert_eq!(program.flow(4), Flow::Indirect);
+    }
+
+    #[test]
+    fn test_branch_and_loop() {
+        let program: Program = "#ip 1
+seti 0 0 2
+addi 2 1 2
+gtri 2 9 3
+addr 3 1 1
+seti 0 0 1
+seti 99 0 1"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            program.flow(3),
+            Flow::Branch {
+                flag: 3,
+                taken: Target::Line(5),
+                not_taken: Target::Line(4),
+            }
+        );
+        let blocks = program.blocks();
+        let starts: Vec<usize> = blocks.iter().map(|b| b.start).collect();
+        assert_eq!(starts, vec![0, 1, 4, 5]);
+        let loops = program.loops(&blocks);
+        assert_eq!(loops.len(), 1);
+        assert_eq!(loops[0].header, 1);
+        assert_eq!(loops[0].body, BTreeSet::from([1, 2]));
+        let code = program.decompile();
+        assert!(code.contains("L1:  // loop over L1, L4"));
+        assert!(code.contains("if r3 goto L5"));
+        assert!(code.contains("halt"));
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert!("#ip 1\nfoo 1 2 3".parse::<Program>().is_err());
+        assert!("#ip 1\naddi 1 2".parse::<Program>().is_err());
+    }
+
+    #[test]
+    fn test_overflowing_jump() {
+        let program: Program = "#ip 0
+seti 9223372036854775807 0 0
+addi 0 9223372036854775807 0
+muli 0 9223372036854775807 0"
+            .parse()
+            .unwrap();
+        assert_eq!(program.flow(0), Flow::Goto(Target::Halt));
+        assert_eq!(program.flow(1), Flow::Indirect);
+        assert_eq!(program.flow(2), Flow::Indirect);
+        assert!(program.decompile().contains("goto"));
+    }
+}
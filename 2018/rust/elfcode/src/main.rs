@@ -0,0 +1,16 @@
+use elfcode::Program;
+use std::fs;
+
+/// Print the decompiled version of an elfcode program (`input` by default).
+fn main() {
+    let fname = std::env::args().nth(1).unwrap_or_else(|| "input".into());
+    let input = fs::read_to_string(&fname).expect("could not read file");
+    let program: Program = match input.parse() {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("{fname}: {e}");
+            std::process::exit(1);
+        }
+    };
+    print!("{}", program.decompile());
+}
@@ -1,3 +1,5 @@
+use shared::numtheory;
+
 fn main() {
     let args = aoc_utils::app("13").get_matches();
     let mut lines = aoc_utils::file_iter_clap(&args);
@@ -18,17 +20,10 @@ fn main() {
 
     println!("Part 1: {:?}", next.0 * next.1);
 
-    let mut period = buses[0].1;
-    let mut epoch = period;
-
-    for (delay, current) in buses[1..].iter() {
-        for _ in 0..*current {
-            if (epoch + delay) % current == 0 {
-                break;
-            }
-            epoch += period;
-        }
-        period *= current;
-    }
+    let congruences: Vec<(isize, isize)> = buses
+        .iter()
+        .map(|&(delay, bus)| (-(delay as isize), bus as isize))
+        .collect();
+    let (epoch, _) = numtheory::crt(&congruences).expect("buses never line up");
     println!("Part 2: {:?}", epoch);
 }
@@ -1,3 +1,4 @@
+use shared::matching::Bipartite;
 use lazy_static::lazy_static;
 use regex::Regex;
 use std::collections::HashMap;
@@ -50,18 +51,11 @@ fn part2(valid: &[&Ticket], names: &[String], ranges: &[Range], mine: TicketRef)
             }
         }
     }
-    let mut locked: HashMap<String, usize> = HashMap::new();
-    while locked.len() != poss.len() {
-        for pos_idx in 0..poss.len() {
-            if poss[pos_idx].len() == 1 {
-                let found = poss[pos_idx][0];
-                locked.insert(names[pos_idx].clone(), found);
-                for other in poss.iter_mut() {
-                    other.retain(|&x| x != found);
-                }
-            }
-        }
-    }
+    let columns = Bipartite::from_candidates(poss, mine.len())
+        .assign()
+        .solution()
+        .expect("could not assign the fields");
+    let locked: HashMap<String, usize> = names.iter().cloned().zip(columns).collect();
     // dbg!{&locked};
 
     let mut result = 1;
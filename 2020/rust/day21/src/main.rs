@@ -1,3 +1,4 @@
+use shared::matching::Bipartite;
 use std::collections::{HashMap, HashSet};
 use std::fs::read_to_string;
 fn main() {
@@ -9,22 +10,30 @@ fn main() {
     println!("Part 2: {}", part2);
 }
 
-fn clean_dangerous<'a>(dangerous: &'a mut HashMap<&'a str, HashSet<&'a str>>) -> String {
-    let mut confirmed: Vec<(&str, &str)> = Vec::new();
+/// Pair every allergen with the only ingredient that can contain it
+fn clean_dangerous<'a>(dangerous: &HashMap<&'a str, HashSet<&'a str>>) -> String {
+    let allergens: Vec<&str> = dangerous.keys().copied().collect();
+    let mut ingredients: Vec<&str> = dangerous.values().flatten().copied().collect();
+    ingredients.sort_unstable();
+    ingredients.dedup();
 
-    while confirmed.len() != dangerous.len() {
-        for (allergen, options) in dangerous.iter_mut() {
-            if options.len() > 1 {
-                for (_, w) in &confirmed {
-                    options.remove(*w);
-                }
-            }
-
-            if options.len() == 1 {
-                confirmed.push((allergen, options.drain().next().unwrap()));
-            }
-        }
-    }
+    let candidates = allergens
+        .iter()
+        .map(|allergen| {
+            dangerous[allergen]
+                .iter()
+                .map(|w| ingredients.binary_search(w).unwrap())
+                .collect()
+        })
+        .collect();
+    let assigned = Bipartite::from_candidates(candidates, ingredients.len())
+        .assign()
+        .solution()
+        .expect("could not find the dangerous ingredients");
+    let confirmed: Vec<(&str, &str)> = allergens
+        .into_iter()
+        .zip(assigned.into_iter().map(|i| ingredients[i]))
+        .collect();
     cleaned_str(&confirmed)
 }
 
@@ -72,7 +81,7 @@ fn solve(input: &str) -> (usize, String) {
 
     let suspicious: HashSet<_> = dangerous.values().flatten().copied().collect();
 
-    let cleaned = clean_dangerous(&mut dangerous);
+    let cleaned = clean_dangerous(&dangerous);
 
     let total: usize = counts
         .iter()
@@ -1,3 +1,4 @@
+use aoc_utils::memo::Memo;
 use std::collections::HashMap;
 const MAX_POS: usize = 10;
 
@@ -59,48 +60,42 @@ pub fn part1(input: &[usize]) -> usize {
     unimplemented!();
 }
 
-pub fn part2(input: &[usize]) -> usize {
-    let mut universes: HashMap<Board, usize> = Default::default();
-    universes.insert(Board::new(input), 1);
-
-    let mut temp: HashMap<Board, usize> = Default::default();
-    let mut done: HashMap<Board, usize> = Default::default();
+/// Positions, scores and turn, i.e., everything in a `Board` but the number of rolls
+type State = ([usize; 2], [usize; 2], usize);
 
-    let mut rolled = vec![vec![]; 1];
-    for _rolls in 0..3 {
-        let mut new_rolled = vec![];
-        for roll in rolled.iter_mut() {
-            for i in 1..=3 {
-                let mut new_roll = roll.clone();
-                new_roll.push(i);
-                new_rolled.push(new_roll);
+/// Number of universes in which each player wins, starting from `board`.
+/// `rolls` are the possible totals of a turn, and in how many universes they happen.
+fn dirac_wins(
+    board: &Board,
+    rolls: &[(usize, usize)],
+    memo: &mut Memo<State, [usize; 2]>,
+) -> [usize; 2] {
+    memo.get((board.pos, board.totals, board.turn), |memo| {
+        let mut wins = [0; 2];
+        for &(total, times) in rolls {
+            let mut next = board.clone();
+            if next.turn(&[total], 21) {
+                wins[board.turn] += times;
+            } else {
+                let next_wins = dirac_wins(&next, rolls, memo);
+                wins[0] += times * next_wins[0];
+                wins[1] += times * next_wins[1];
             }
         }
-        rolled = new_rolled;
-    }
+        wins
+    })
+}
 
-    while !universes.is_empty() {
-        for (univ, times) in universes.iter() {
-            for roll in rolled.iter() {
-                let mut new_univ = univ.clone();
-                if new_univ.turn(roll, 21) {
-                    *done.entry(new_univ).or_default() += times;
-                } else {
-                    *temp.entry(new_univ).or_default() += times;
-                }
+pub fn part2(input: &[usize]) -> usize {
+    let mut rolls: HashMap<usize, usize> = Default::default();
+    for a in 1..=3 {
+        for b in 1..=3 {
+            for c in 1..=3 {
+                *rolls.entry(a + b + c).or_default() += 1;
             }
         }
-
-        temp = std::mem::replace(&mut universes, temp);
-        temp.clear();
     }
-
-    let (won, _times) = done.iter().fold((0, 0), |mut counter, (u, times)| {
-        counter.1 += times;
-        if u.totals[0] > u.totals[1] {
-            counter.0 += times;
-        }
-        counter
-    });
-    won
+    let rolls: Vec<(usize, usize)> = rolls.into_iter().collect();
+    let wins = dirac_wins(&Board::new(input), &rolls, &mut Memo::new());
+    wins.into_iter().max().unwrap()
 }
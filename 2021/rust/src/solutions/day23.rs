@@ -1,42 +1,59 @@
+use aoc_utils::search::{branch_and_bound, Objective, Problem};
 use std::cmp::Ordering;
-use std::collections::BinaryHeap;
 use std::fmt::Write;
 
 pub type Amphipod = char;
 
-#[derive(Clone, Eq)]
+#[derive(Clone)]
 pub struct Hallway {
     cost: usize,
     estimate: Option<usize>,
     cells: Vec<HCell>,
 }
 
-impl PartialEq for Hallway {
-    fn eq(&self, other: &Self) -> bool {
-        self.cost == other.cost
+/// Sort the amphipods with the least energy
+struct Burrow;
+
+impl Problem for Burrow {
+    type State = Hallway;
+    type Key = Vec<HCell>;
+
+    fn objective(&self) -> Objective {
+        Objective::Min
     }
-}
 
-impl PartialOrd for Hallway {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+    fn successors(&self, hallway: &Hallway) -> Vec<(Hallway, i64)> {
+        hallway
+            .evolve()
+            .into_iter()
+            .map(|next| {
+                let cost = next.cost - hallway.cost;
+                (next, cost as i64)
+            })
+            .collect()
+    }
+
+    fn is_goal(&self, hallway: &Hallway) -> bool {
+        hallway.done()
+    }
+
+    fn key(&self, hallway: &Hallway) -> Vec<HCell> {
+        hallway.cells.clone()
     }
-}
 
-impl Ord for Hallway {
-    fn cmp(&self, other: &Self) -> Ordering {
-        (other.cost + other.estimate()).cmp(&(self.cost + self.estimate()))
+    fn bound(&self, hallway: &Hallway) -> Option<i64> {
+        Some(hallway.estimate() as i64)
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
 pub enum HCell {
     Empty,
     Occupied(Amphipod),
     Entrance(Room),
 }
 
-#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
 pub struct Room {
     target: Amphipod,
     positions: Vec<Option<char>>,
@@ -371,17 +388,11 @@ fn update4part2(input: &Hallway) -> Hallway {
 }
 
 fn solve(input: &Hallway) -> usize {
-    let mut heap = BinaryHeap::new();
-    heap.push(input.clone());
-
-    while let Some(candidate) = heap.pop() {
-        // dbg!(&candidate);
-        if candidate.done() {
-            return candidate.cost;
-        }
-        heap.extend(candidate.evolve().into_iter());
-    }
-    panic!("Solution not found!");
+    let mut start = input.clone();
+    start.update_estimate();
+    branch_and_bound(&Burrow, start)
+        .value
+        .expect("Solution not found!") as usize
 }
 
 pub fn part1(input: &Hallway) -> usize {
@@ -424,7 +435,6 @@ mod test {
         assert_eq!(result.cost, 5 * cost(&'B'));
     }
 
-    #[ignore]
     #[test]
     fn test_example() {
         let hall = hallway![. . [A: B A] . [B: C D] . [C: B C] . [D: D A] . . ];
@@ -3,16 +3,45 @@ use std::cmp::Ordering;
 use std::collections::{BTreeSet, HashSet};
 use std::ops::{Index, IndexMut};
 
-pub fn part1(pc: &PC) -> Regtype {
-    pc.find(false)
+pub fn part1(monad: &Monad) -> Regtype {
+    match monad.analysis() {
+        Ok(analysis) => {
+            for constraint in &analysis.constraints {
+                dbg!(constraint.to_string());
+            }
+            analysis.max
+        }
+        Err(err) => {
+            dbg!("symbolic analysis failed, falling back to search", err);
+            monad.pc.find(false)
+        }
+    }
+}
+
+pub fn part2(monad: &Monad) -> Regtype {
+    match monad.analysis() {
+        Ok(analysis) => analysis.min,
+        Err(_) => monad.pc.find(true),
+    }
+}
+
+/// The program, and the result of its symbolic analysis (shared by both parts)
+pub struct Monad<'a> {
+    pc: PC<'a>,
+    analysis: Result<Analysis, String>,
 }
 
-pub fn part2(pc: &PC) -> Regtype {
-    pc.find(true)
+impl Monad<'_> {
+    /// The constraints between the digits of a valid model number, or why they could not be found
+    pub fn analysis(&self) -> Result<&Analysis, &str> {
+        self.analysis.as_ref().map_err(|err| err.as_str())
+    }
 }
 
-pub fn parse(input: &str) -> PC {
-    PC::parse(input)
+pub fn parse(input: &str) -> Monad<'_> {
+    let pc = PC::parse(input);
+    let analysis = pc.analyse();
+    Monad { pc, analysis }
 }
 
 #[derive(Debug, Clone)]
@@ -61,6 +90,67 @@ impl<'a> PC<'a> {
         panic!("solution not found");
     }
 
+    /// Run the program symbolically to find the constraints between the input digits, and solve
+    /// them directly.
+    ///
+    /// z is treated as a stack of numbers in base 26, where every element is an input digit plus
+    /// an offset. Whenever an `eql` cannot be decided statically, the execution is forked: one
+    /// branch assumes the digits satisfy the equality, the other one assumes they do not.
+    /// Only the branches that end with z == 0 are kept.
+    fn analyse(&self) -> Result<Analysis, String> {
+        let mut valid = vec![];
+        let mut pending = vec![(0, Symbolic::new())];
+        while let Some((mut pos, mut st)) = pending.pop() {
+            while pos < self.program.len() {
+                let inst = &self.program[pos];
+                pos += 1;
+                if let Some(fork) = st.apply(inst)? {
+                    pending.push((pos, fork));
+                }
+            }
+            if st.regs[3] == Sym::Const(0) {
+                valid.push(st);
+            }
+        }
+
+        let mut best: Option<Analysis> = None;
+        for st in valid {
+            let Some(max) = st.solve(true) else { continue };
+            let Some(min) = st.solve(false) else { continue };
+            let (max, min) = (to_number(&max), to_number(&min));
+            let constraints: Vec<Constraint> = st
+                .constraints
+                .iter()
+                .filter(|(_, holds)| *holds)
+                .map(|(c, _)| c.clone())
+                .collect();
+            best = Some(match best {
+                None => Analysis {
+                    constraints,
+                    max,
+                    min,
+                },
+                Some(b) => Analysis {
+                    max: b.max.max(max),
+                    min: b.min.min(min),
+                    ..b
+                },
+            });
+        }
+        let analysis = best.ok_or("no input makes z == 0")?;
+        for n in [analysis.max, analysis.min] {
+            let digits: Vec<Regtype> = n
+                .to_string()
+                .bytes()
+                .map(|b| (b - b'0') as Regtype)
+                .collect();
+            if !self.confirm(&digits) {
+                return Err(format!("{n} does not validate"));
+            }
+        }
+        Ok(analysis)
+    }
+
     fn confirm(&self, input: &[Regtype]) -> bool {
         let mut state = State::new();
         state.input.extend(input.iter());
@@ -250,3 +340,327 @@ impl StateMap {
         self.heap.len()
     }
 }
+
+fn to_number(digits: &[Regtype]) -> Regtype {
+    digits.iter().fold(0, |acc, d| acc * 10 + d)
+}
+
+const BASE: Regtype = 26;
+
+/// Symbolic value of a register
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Sym {
+    Const(Regtype),
+    /// The value of an input digit plus an offset
+    Digit(usize, Regtype),
+    /// A number in base 26, least significant element first
+    Stack(Vec<Sym>),
+}
+
+impl Sym {
+    fn range(&self) -> Option<(Regtype, Regtype)> {
+        match self {
+            Sym::Const(c) => Some((*c, *c)),
+            Sym::Digit(_, k) => Some((1 + k, 9 + k)),
+            Sym::Stack(_) => None,
+        }
+    }
+
+    /// Whether the value is guaranteed to fit in a single element of the stack
+    fn is_element(&self) -> bool {
+        matches!(self.range(), Some((lo, hi)) if lo >= 0 && hi < BASE)
+    }
+
+    fn into_stack(self) -> Vec<Sym> {
+        match self {
+            Sym::Const(0) => vec![],
+            Sym::Stack(s) => s,
+            other => vec![other],
+        }
+    }
+
+    fn from_stack(mut stack: Vec<Sym>) -> Sym {
+        while stack.last() == Some(&Sym::Const(0)) {
+            stack.pop();
+        }
+        match stack.len() {
+            0 => Sym::Const(0),
+            1 => stack.pop().unwrap(),
+            _ => Sym::Stack(stack),
+        }
+    }
+}
+
+/// A constraint between two input digits: `d[left] + offset == d[right]`.
+/// `right` is also the input block where the constraint is checked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constraint {
+    left: usize,
+    right: usize,
+    offset: Regtype,
+}
+
+impl std::fmt::Display for Constraint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "block {}: d{} = d{} {} {}",
+            self.right,
+            self.right,
+            self.left,
+            if self.offset < 0 { '-' } else { '+' },
+            self.offset.abs()
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Analysis {
+    pub constraints: Vec<Constraint>,
+    pub max: Regtype,
+    pub min: Regtype,
+}
+
+#[derive(Debug, Clone)]
+struct Symbolic {
+    regs: [Sym; 4],
+    ninputs: usize,
+    /// Constraints found so far, and whether this branch assumes they hold or not
+    constraints: Vec<(Constraint, bool)>,
+}
+
+impl Symbolic {
+    fn new() -> Self {
+        Symbolic {
+            regs: [Sym::Const(0), Sym::Const(0), Sym::Const(0), Sym::Const(0)],
+            ninputs: 0,
+            constraints: vec![],
+        }
+    }
+
+    fn reg(name: &str) -> Result<usize, String> {
+        ["w", "x", "y", "z"]
+            .iter()
+            .position(|r| *r == name)
+            .ok_or_else(|| format!("invalid register name {name}"))
+    }
+
+    /// Apply an instruction. If the result cannot be decided, the state assumes the comparison
+    /// succeeds and a copy that assumes it fails is returned.
+    fn apply(&mut self, inst: &Instruction) -> Result<Option<Symbolic>, String> {
+        let (cmd, out, b) = match inst {
+            Single("inp", r) => {
+                self.regs[Self::reg(r)?] = Sym::Digit(self.ninputs, 0);
+                self.ninputs += 1;
+                return Ok(None);
+            }
+            Direct(cmd, a, b) => (*cmd, Self::reg(a)?, Sym::Const(*b)),
+            Indirect(cmd, a, b) => (*cmd, Self::reg(a)?, self.regs[Self::reg(b)?].clone()),
+            Single(cmd, _) => return Err(format!("invalid instruction {cmd}")),
+        };
+        let a = self.regs[out].clone();
+        let unsupported = || format!("unsupported operation {cmd} {a:?} {b:?}");
+        let res = match (cmd, &a, &b) {
+            ("add", Sym::Const(x), Sym::Const(y)) => Sym::Const(x + y),
+            ("add", Sym::Const(0), v) | ("add", v, Sym::Const(0)) => v.clone(),
+            ("add", Sym::Digit(i, k), Sym::Const(c)) | ("add", Sym::Const(c), Sym::Digit(i, k)) => {
+                Sym::Digit(*i, k + c)
+            }
+            ("add", Sym::Stack(s), v) if s[0] == Sym::Const(0) && v.is_element() => {
+                let mut s = s.clone();
+                s[0] = v.clone();
+                Sym::Stack(s)
+            }
+            ("mul", Sym::Const(x), Sym::Const(y)) => Sym::Const(x * y),
+            ("mul", _, Sym::Const(0)) | ("mul", Sym::Const(0), _) => Sym::Const(0),
+            ("mul", v, Sym::Const(1)) | ("mul", Sym::Const(1), v) => v.clone(),
+            ("mul", v, Sym::Const(BASE)) | ("mul", Sym::Const(BASE), v)
+                if matches!(v, Sym::Stack(_)) || v.is_element() =>
+            {
+                let mut s = v.clone().into_stack();
+                s.insert(0, Sym::Const(0));
+                Sym::Stack(s)
+            }
+            ("div", Sym::Const(x), Sym::Const(y)) if *y != 0 => Sym::Const(x / y),
+            ("div", v, Sym::Const(1)) => v.clone(),
+            ("div", Sym::Stack(s), Sym::Const(BASE)) => Sym::from_stack(s[1..].to_vec()),
+            ("div", v, Sym::Const(BASE)) if v.is_element() => Sym::Const(0),
+            ("mod", Sym::Const(x), Sym::Const(y)) if *y > 0 => Sym::Const(x % y),
+            ("mod", Sym::Stack(s), Sym::Const(BASE)) => s[0].clone(),
+            ("mod", v, Sym::Const(BASE)) if v.is_element() => v.clone(),
+            ("eql", Sym::Digit(i, k), Sym::Digit(j, l)) if i != j => {
+                let (left, right) = (i.min(j), i.max(j));
+                let offset = if i < j { k - l } else { l - k };
+                let constraint = Constraint {
+                    left: *left,
+                    right: *right,
+                    offset,
+                };
+                let mut fork = self.clone();
+                fork.regs[out] = Sym::Const(0);
+                fork.constraints.push((constraint.clone(), false));
+                self.regs[out] = Sym::Const(1);
+                self.constraints.push((constraint, true));
+                return Ok(Some(fork));
+            }
+            ("eql", x, y) => match (x.range(), y.range()) {
+                (Some((xl, xh)), Some((yl, yh))) if xh < yl || yh < xl => Sym::Const(0),
+                (Some((xl, xh)), Some((yl, yh))) if xl == xh && yl == yh => {
+                    Sym::Const((xl == yl) as Regtype)
+                }
+                _ if x == y => Sym::Const(1),
+                _ => return Err(unsupported()),
+            },
+            _ => return Err(unsupported()),
+        };
+        self.regs[out] = res;
+        Ok(None)
+    }
+
+    /// Assign every digit so that the constraints of this branch are satisfied, preferring
+    /// the biggest (or smallest) digits possible. Returns None if there is no solution.
+    fn solve(&self, maximise: bool) -> Option<Vec<Regtype>> {
+        let mut digits: Vec<Option<Regtype>> = vec![None; self.ninputs];
+        for root in 0..self.ninputs {
+            if digits[root].is_some() {
+                continue;
+            }
+            // Offsets of every digit connected to this one
+            let mut offsets = vec![(root, 0)];
+            let mut idx = 0;
+            while idx < offsets.len() {
+                let (d, off) = offsets[idx];
+                for (c, _) in self.constraints.iter().filter(|(_, holds)| *holds) {
+                    let next = if c.left == d {
+                        (c.right, off + c.offset)
+                    } else if c.right == d {
+                        (c.left, off - c.offset)
+                    } else {
+                        continue;
+                    };
+                    match offsets.iter().find(|(o, _)| *o == next.0) {
+                        Some((_, prev)) if *prev != next.1 => return None,
+                        Some(_) => {}
+                        None => offsets.push(next),
+                    }
+                }
+                idx += 1;
+            }
+            let lo = offsets.iter().map(|(_, o)| 1 - o).max().unwrap();
+            let hi = offsets.iter().map(|(_, o)| 9 - o).min().unwrap();
+            if lo > hi {
+                return None;
+            }
+            let value = if maximise { hi } else { lo };
+            for (d, off) in offsets {
+                digits[d] = Some(value + off);
+            }
+        }
+        let digits: Vec<Regtype> = digits.into_iter().map(|d| d.unwrap()).collect();
+        self.constraints
+            .iter()
+            .all(|(c, holds)| (digits[c.left] + c.offset == digits[c.right]) == *holds)
+            .then_some(digits)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Two MONAD blocks: the first one pushes d0 + 4, the second one pops it and compares it
+    /// with d1 + 6
+    const BLOCKS: &str = "inp w
+mul x 0
+add x z
+mod x 26
+div z 1
+add x 12
+eql x w
+eql x 0
+mul y 0
+add y 25
+mul y x
+add y 1
+mul z y
+mul y 0
+add y w
+add y 4
+mul y x
+add z y
+inp w
+mul x 0
+add x z
+mod x 26
+div z 26
+add x -6
+eql x w
+eql x 0
+mul y 0
+add y 25
+mul y x
+add y 1
+mul z y
+mul y 0
+add y w
+add y 7
+mul y x
+add z y";
+
+    #[test]
+    fn test_apply_forks_on_eql() {
+        let pc = PC::parse(BLOCKS);
+        let mut st = Symbolic::new();
+        for inst in &pc.program[..18] {
+            assert!(st.apply(inst).unwrap().is_none());
+        }
+        assert_eq!(st.regs[3], Sym::Digit(0, 4));
+
+        let mut forks = vec![];
+        for inst in &pc.program[18..] {
+            forks.extend(st.apply(inst).unwrap());
+        }
+        let constraint = Constraint {
+            left: 0,
+            right: 1,
+            offset: -2,
+        };
+        assert_eq!(constraint.to_string(), "block 1: d1 = d0 - 2");
+        assert_eq!(st.constraints, vec![(constraint.clone(), true)]);
+        assert_eq!(st.regs[3], Sym::Const(0));
+
+        // The branch where the comparison fails pushes d1 + 7 instead of popping
+        assert_eq!(forks.len(), 1);
+        assert_eq!(forks[0].constraints, vec![(constraint, false)]);
+        assert_eq!(forks[0].regs[1], Sym::Const(0));
+    }
+
+    #[test]
+    fn test_solve() {
+        let mut st = Symbolic::new();
+        st.ninputs = 3;
+        st.constraints = vec![(
+            Constraint {
+                left: 0,
+                right: 2,
+                offset: -2,
+            },
+            true,
+        )];
+        assert_eq!(st.solve(true), Some(vec![9, 9, 7]));
+        assert_eq!(st.solve(false), Some(vec![3, 1, 1]));
+
+        st.constraints[0].0.offset = 9;
+        assert_eq!(st.solve(true), None);
+    }
+
+    #[test]
+    fn test_analyse() {
+        let monad = parse(BLOCKS);
+        let analysis = monad.analysis.as_ref().unwrap();
+        assert_eq!(analysis.max, 97);
+        assert_eq!(analysis.min, 31);
+        assert_eq!(part1(&monad), 97);
+        assert_eq!(part2(&monad), 31);
+    }
+}
@@ -1,3 +1,4 @@
+use aoc_utils::automaton::{fixpoint, von_neumann, Dense, Edges};
 use std::fmt::{Debug, Error, Formatter};
 #[derive(Debug, Clone, Hash, Copy, PartialEq, Eq)]
 pub enum Cell {
@@ -8,9 +9,7 @@ pub enum Cell {
 
 #[derive(Clone, PartialEq, Eq, Hash)]
 pub struct Grid {
-    cells: Vec<Vec<Cell>>,
-    width: isize,
-    height: isize,
+    cells: Dense<Cell, 2>,
 }
 
 impl Debug for Grid {
@@ -20,7 +19,7 @@ impl Debug for Grid {
             f,
             "{}",
             self.cells
-                .iter()
+                .rows()
                 .map(|row| -> String {
                     row.iter()
                         .map(|cell| match cell {
@@ -54,42 +53,34 @@ impl Grid {
             })
             .collect();
         Grid {
-            height: cells.len() as isize,
-            width: cells[0].len() as isize,
-            cells,
+            cells: Dense::from_rows(cells, Cell::Empty, Edges::Wrap),
         }
     }
 
-    fn move_herd(&self, herd: Cell) -> Self {
-        let delta = match herd {
+    /// Move every cucumber of a herd that has an empty cell in front of it.
+    /// Returns false if none of them could move.
+    fn move_herd(&mut self, herd: Cell) -> bool {
+        // Positions in the neighborhood of the cells behind and in front of each cucumber
+        let (behind, ahead) = match herd {
             Cell::Down => (0, 1),
-            Cell::Right => (1, 0),
+            Cell::Right => (2, 3),
             _ => panic!("invalid cell type to move"),
         };
-        let mut result = self.clone();
-
-        for (y, row) in self.cells.iter().enumerate() {
-            for (x, cell) in row.iter().enumerate() {
-                if cell != &herd {
-                    continue;
-                }
-                let (nx, ny) = (
-                    ((x as isize) + delta.0).rem_euclid(self.width) as usize,
-                    ((y as isize) + delta.1).rem_euclid(self.height) as usize,
-                );
-                // dbg!((x, y, nx, ny, delta));
-                if self.cells[ny][nx] == Cell::Empty {
-                    result.cells[y][x] = Cell::Empty;
-                    result.cells[ny][nx] = herd;
-                }
+        self.cells.step(&von_neumann(), |cell, neighbors| {
+            if cell == herd && neighbors[ahead] == Cell::Empty {
+                Cell::Empty
+            } else if cell == Cell::Empty && neighbors[behind] == herd {
+                herd
+            } else {
+                cell
             }
-        }
-        result
+        })
     }
 
-    fn evolve(&self) -> Self {
-        let out = self.move_herd(Cell::Right);
-        out.move_herd(Cell::Down)
+    fn evolve(&mut self) -> bool {
+        let right = self.move_herd(Cell::Right);
+        let down = self.move_herd(Cell::Down);
+        right || down
     }
 }
 
@@ -98,16 +89,8 @@ pub fn parse(input: &str) -> Grid {
 }
 
 pub fn part1(input: &Grid) -> usize {
-    let mut last = input.clone();
-    for i in 1.. {
-        let current = last.evolve();
-        // dbg!(&current);
-        if current == last {
-            return i;
-        }
-        last = current;
-    }
-    panic!("No solution found");
+    let mut grid = input.clone();
+    fixpoint(|| grid.evolve())
 }
 
 pub fn part2(_input: &Grid) -> &str {
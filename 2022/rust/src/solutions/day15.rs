@@ -1,4 +1,5 @@
 /// This code stinks. I barely had time today and I'm still not used to nalg.
+use aoc_utils::intervals::IntervalSet;
 use nalgebra::{Point2, Vector2};
 use regex::Regex;
 use std::collections::HashSet;
@@ -53,44 +54,17 @@ pub fn part1(input: &[Pair]) -> usize {
 }
 
 pub fn part1_gen(input: &[Pair], target: isize) -> usize {
-    let mut distances: Vec<_> = input
+    let mut covered: IntervalSet<isize> = input
         .iter()
-        .map(|[sensor, beacon]| (sensor, dist(sensor, beacon)))
+        .filter_map(|[sensor, beacon]| {
+            let reach = dist(sensor, beacon) as isize - (sensor[1] - target).abs();
+            (reach >= 0).then(|| sensor[0] - reach..sensor[0] + reach + 1)
+        })
         .collect();
-    let taken: HashSet<Coord> = input.iter().flatten().copied().collect();
-    distances.sort_by(|a, b| b.1.cmp(&a.1));
-
-    let min_x = distances
-        .iter()
-        .map(|(p, rad)| p[0] - *rad as isize)
-        .min()
-        .unwrap();
-    let max_x = distances
-        .iter()
-        .map(|(p, rad)| p[0] + *rad as isize)
-        .max()
-        .unwrap();
-
-    let mut unr = 0;
-    for i in min_x..=max_x {
-        let p = Point2::new(i, target);
-        if !taken.contains(&p)
-            && distances
-                .iter()
-                .any(|(beacon, radius)| dist(&p, beacon) <= *radius)
-        {
-            unr += 1;
-        }
+    for p in input.iter().flatten().filter(|p| p[1] == target) {
+        covered.remove(p[0]..p[0] + 1);
     }
-    input
-        .iter()
-        .filter(|[_sensor, beacon]| beacon[1] == target)
-        .count();
-    input
-        .iter()
-        .filter(|[sensor, _beacon]| sensor[1] == target)
-        .count();
-    unr
+    covered.len() as usize
 }
 
 pub fn part2(input: &[Pair]) -> usize {
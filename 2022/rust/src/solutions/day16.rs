@@ -1,10 +1,9 @@
-use itertools::Itertools;
+use aoc_utils::search::{branch_and_bound, Objective, Problem};
 use regex::Regex;
-use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, VecDeque};
 
 type Edges = Vec<usize>;
 type Graph = Vec<Edges>;
-type Valves = Vec<usize>;
 
 #[derive(Debug)]
 pub struct Input<'a> {
@@ -13,88 +12,75 @@ pub struct Input<'a> {
     flows: Vec<usize>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-struct State<'a, const N: usize> {
-    edges: &'a Graph,
-    flows: &'a Vec<usize>,
-    valves: Valves,
-    remaining: usize,
-    total: usize,
-    positions: [usize; N],
-    last_move: [usize; N],
+/// Where each actor is and the minutes it has left after opening that valve, and the valves
+/// that are (or will be) open
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct State<const N: usize> {
+    actors: [(usize, usize); N],
+    open: u64,
 }
 
-impl<'a, const N: usize> State<'a, N> {
-    fn new(input: &'a Input, remaining: usize) -> Self {
-        State {
-            flows: &input.flows,
-            edges: &input.edges,
-            valves: vec![usize::MAX; input.names.len()],
-            remaining,
-            last_move: [remaining; N],
-            total: 0,
-            positions: [0; N],
-        }
-    }
+struct Volcano<'a, const N: usize> {
+    input: &'a Input<'a>,
+}
 
-    fn to_key(&self) -> (Vec<(usize, usize)>, Valves) {
-        (
-            self.positions
-                .iter()
-                .copied()
-                .zip(self.last_move.iter().copied())
-                .sorted()
-                .collect_vec(),
-            self.valves.clone(),
-        )
-    }
+impl<const N: usize> Problem for Volcano<'_, N> {
+    type State = State<N>;
+    type Key = State<N>;
 
-    fn max_expected(&self) -> usize {
-        self.total
-            + self
-                .flows
-                .iter()
-                .enumerate()
-                .filter_map(|(name, flow)| {
-                    if self.valves[name] != usize::MAX {
-                        None
-                    } else {
-                        Some(flow)
-                    }
-                })
-                .sum::<usize>()
-                * self.last_move.iter().min().copied().unwrap_or_default()
+    fn objective(&self) -> Objective {
+        Objective::Max
     }
-}
 
-impl<'a, const N: usize> State<'a, N> {
-    fn explore<'b>(&'b self, idx: usize) -> Vec<State<'a, N>>
-    where
-        'a: 'b,
-    {
+    /// Any actor walks to a closed valve and opens it, which releases its flow every minute
+    /// left after that
+    fn successors(&self, state: &State<N>) -> Vec<(State<N>, i64)> {
         let mut opts = vec![];
-        for (other, distance) in self.edges[self.positions[idx]].iter().enumerate() {
-            // if !self.flows.contains_key(other)
-            if other == self.positions[idx]
-                || self.last_move[idx] <= distance + 1
-                || (&self.valves)[other] != usize::MAX
-            {
-                continue;
-            }
-
-            let delta = self.flows[other];
-            let mut out = self.clone();
-            out.positions[idx] = other;
-            out.last_move[idx] = self.last_move[idx] - distance - 1;
-            if out.last_move[idx] < self.remaining {
-                out.remaining = out.last_move[idx];
+        for idx in 0..N {
+            let (position, remaining) = state.actors[idx];
+            for (other, distance) in self.input.edges[position].iter().enumerate() {
+                let flow = self.input.flows[other];
+                if flow == 0 || remaining <= distance + 1 || state.open & (1 << other) != 0 {
+                    continue;
+                }
+                let mut out = state.clone();
+                out.actors[idx] = (other, remaining - distance - 1);
+                out.open |= 1 << other;
+                opts.push((out, (flow * (remaining - distance - 1)) as i64));
             }
-            out.total += out.last_move[idx] * delta;
-            out.valves[other] = out.last_move[idx];
-            opts.push(out);
         }
         opts
     }
+
+    fn is_goal(&self, _state: &State<N>) -> bool {
+        true
+    }
+
+    /// Actors are interchangeable
+    fn key(&self, state: &State<N>) -> State<N> {
+        let mut key = state.clone();
+        key.actors.sort_unstable();
+        key
+    }
+
+    /// Every closed valve is opened by the actor that can get there first
+    fn bound(&self, state: &State<N>) -> Option<i64> {
+        let total = (0..self.input.flows.len())
+            .filter(|valve| state.open & (1 << valve) == 0)
+            .map(|valve| {
+                let minutes = state
+                    .actors
+                    .iter()
+                    .map(|&(position, remaining)| {
+                        remaining.saturating_sub(self.input.edges[position][valve] + 1)
+                    })
+                    .max()
+                    .unwrap_or_default();
+                self.input.flows[valve] * minutes
+            })
+            .sum::<usize>();
+        Some(total as i64)
+    }
 }
 
 static ROOT: &str = "AA";
@@ -159,41 +145,13 @@ pub fn parse(input: &str) -> Input {
 }
 
 pub fn solve<const N: usize>(input: &Input, remaining: usize) -> usize {
-    let mut opts = vec![State::new(input, remaining)];
-    let mut best: Option<State<N>> = None;
-    let mut visited = HashSet::new();
-    while let Some(state) = opts.pop() {
-        let key = state.to_key();
-        if visited.contains(&key) {
-            continue;
-        }
-        visited.insert(key);
-        let max = match (&best, &state) {
-            (None, s) => {
-                best = Some(s.clone());
-                s.total
-            }
-            (Some(State { total: b, .. }), s) if s.total > *b => {
-                let max = *b;
-                best = Some(s.clone());
-                max
-            }
-            (Some(State { total: b, .. }), _) if b > &state.max_expected() => {
-                continue;
-            }
-            (Some(State { total: b, .. }), _) => *b,
-        };
-        for idx in 0..N {
-            for opt in state.explore(idx) {
-                if opt.max_expected() >= max {
-                    opts.push(opt);
-                }
-            }
-        }
-    }
-    let best = best.unwrap();
-    // dbg!(best.valves);
-    best.total
+    assert!(input.names.len() <= 64, "too many valves for the bitmask");
+    let start = State {
+        actors: [(0, remaining); N],
+        open: 0,
+    };
+    let best = branch_and_bound(&Volcano::<N> { input }, start);
+    best.value.expect("there is always a solution") as usize
 }
 
 pub fn part1(input: &Input) -> usize {
@@ -1,6 +1,5 @@
+use aoc_utils::search::{branch_and_bound, Objective, Problem};
 use regex::Regex;
-use std::cmp::Ordering;
-use std::collections::{BinaryHeap, HashSet};
 
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
 pub struct Blueprint {
@@ -8,104 +7,90 @@ pub struct Blueprint {
     costs: [[usize; 4]; 4],
 }
 
-#[derive(Clone, Debug, Eq, Hash)]
-struct State<'a> {
-    blueprint: &'a Blueprint,
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct State {
     remaining: usize,
     robots: [usize; 4],
-    pending_robots: [usize; 4],
+    /// Geodes are not kept, they are counted as soon as a geode robot is built
     stock: [usize; 4],
 }
 
-impl<'a> State<'a> {
-    fn estimate(&self) -> usize {
-        self.stock[3] + self.robots[3] * (self.remaining) + (self.remaining).pow(2) / 2
+impl Blueprint {
+    /// The most of each resource that can be spent in a minute. Building more robots than that
+    /// is useless (except for geodes).
+    fn max_useful(&self) -> [usize; 4] {
+        let mut max = [usize::MAX; 4];
+        for (resource, max) in max.iter_mut().enumerate().take(3) {
+            *max = self.costs.iter().map(|c| c[resource]).max().unwrap();
+        }
+        max
     }
 }
 
-impl Ord for State<'_> {
-    fn cmp(&self, other: &Self) -> Ordering {
-        other
-            .remaining
-            .cmp(&self.remaining)
-            .then_with(|| self.estimate().cmp(&other.estimate()))
-        // self.estimate(32).cmp(&other.estimate(32))
-    }
-}
+impl Problem for Blueprint {
+    type State = State;
+    type Key = State;
 
-impl PartialOrd for State<'_> {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+    fn objective(&self) -> Objective {
+        Objective::Max
     }
-}
-
-impl PartialEq for State<'_> {
-    fn eq(&self, other: &Self) -> bool {
-        self.remaining == other.remaining && self.estimate() == other.estimate()
-    }
-}
 
-impl<'a> State<'a> {
-    fn from_blueprint(blueprint: &'a Blueprint, remaining: usize) -> Self {
-        State {
-            blueprint,
-            remaining,
-            robots: [1, 0, 0, 0],
-            pending_robots: [0; 4],
-            stock: [0; 4],
+    /// Build one of the robots we can afford, or wait if there is one we cannot afford yet
+    fn successors(&self, state: &State) -> Vec<(State, i64)> {
+        if state.remaining == 0 {
+            return vec![];
         }
-    }
-
-    fn tick(&mut self) {
-        self.remaining -= 1;
-        self.robots
-            .iter()
-            .zip(self.stock.iter_mut())
-            .for_each(|(r, s)| {
-                *s += r;
-            });
-        self.robots
-            .iter_mut()
-            .zip(self.pending_robots.iter_mut())
-            .for_each(|(r, pr)| {
-                *r += *pr;
-                *pr = 0;
-            });
-    }
-
-    fn explore(&self) -> Vec<Self> {
+        let mut waiting = state.clone();
+        waiting.remaining -= 1;
+        for (stock, robots) in waiting.stock.iter_mut().zip(state.robots) {
+            *stock += robots;
+        }
+        // The search explores the last option first, and geode robots are usually the best
         let mut opts = Vec::with_capacity(5);
-        // opts.push(self.clone());
-
-        // opts.push(self.clone());
-        let opt = self;
+        let max_useful = self.max_useful();
         let mut could_buy = 0;
-        for idx in 0..4 {
-            if opt
-                .stock
-                .iter()
-                .zip(opt.blueprint.costs[idx].iter())
-                .all(|(a, b)| a >= b)
-            {
-                could_buy += 1; //idx+1;
-                let mut new = opt.clone();
-                new.stock
-                    .iter_mut()
-                    .zip(opt.blueprint.costs[idx].iter())
-                    .for_each(|(s, c)| {
-                        *s -= c;
-                    });
-                new.pending_robots[idx] += 1;
-                opts.push(new);
+        for (idx, cost) in self.costs.iter().enumerate() {
+            if state.stock.iter().zip(cost).any(|(s, c)| s < c) {
+                continue;
+            }
+            could_buy += 1;
+            if state.robots[idx] >= max_useful[idx] {
+                continue;
             }
+            let mut new = waiting.clone();
+            new.stock.iter_mut().zip(cost).for_each(|(s, c)| *s -= c);
+            new.robots[idx] += 1;
+            let geodes = if idx == 3 { new.remaining } else { 0 };
+            opts.push((new, geodes as i64));
         }
         if could_buy < 4 {
-            opts.push(self.clone());
+            opts.insert(0, (waiting, 0));
         }
-        opts.iter_mut().for_each(|b| b.tick());
-
         opts
     }
+
+    fn is_goal(&self, state: &State) -> bool {
+        state.remaining == 0
+    }
+
+    fn key(&self, state: &State) -> State {
+        state.clone()
+    }
+
+    /// As if ore and clay were free, so we could build an obsidian robot every minute, and a
+    /// geode robot whenever there is enough obsidian
+    fn bound(&self, state: &State) -> Option<i64> {
+        let (mut obsidian, mut robots, mut geodes) = (state.stock[2], state.robots[2], 0);
+        for remaining in (1..=state.remaining).rev() {
+            if obsidian >= self.costs[3][2] {
+                obsidian -= self.costs[3][2];
+                geodes += remaining - 1;
+            }
+            obsidian += robots;
+            robots += 1;
+        }
+        Some(geodes as i64)
+    }
 }
 
 pub fn parse(input: &str) -> Vec<Blueprint> {
@@ -139,43 +124,19 @@ pub fn parse(input: &str) -> Vec<Blueprint> {
         .collect()
 }
 
+/// Most geodes each blueprint can open
 pub fn solve(blueprints: &[Blueprint], limit: usize) -> Vec<(usize, usize)> {
-    // dbg!(&blueprints);
-
     blueprints
         .iter()
         .map(|b| {
-            let mut opts = BinaryHeap::new();
-            let mut visited = HashSet::new();
-            let mut max_score = 0;
-
-            let root = State::from_blueprint(b, limit);
-            let mut max = root.clone();
-            opts.push(root);
-
-            // dbg!(b.blueprint_id);
-            while let Some(next) = opts.pop() {
-                let estimate = next.estimate();
-                if estimate <= max_score || visited.contains(&next) {
-                    // if estimate <= max_score {
-                    continue;
-                }
-                visited.insert(next.clone());
-
-                if next.remaining > 0 {
-                    opts.extend(
-                        next.explore(), // .into_iter()
-                                        // .filter(|b| max_score == 0 || b.estimate() >= max_score),
-                    );
-                    continue;
-                }
-                // println!("FINISHED ONE {estimate}");
-                max_score = next.stock[3];
-                max = next;
-            }
-            max
+            let start = State {
+                remaining: limit,
+                robots: [1, 0, 0, 0],
+                stock: [0; 4],
+            };
+            let best = branch_and_bound(b, start);
+            (b.blueprint_id, best.value.unwrap_or_default() as usize)
         })
-        .map(|b| (b.blueprint.blueprint_id, b.stock[3]))
         .collect()
 }
 
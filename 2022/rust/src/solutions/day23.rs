@@ -1,3 +1,4 @@
+use aoc_utils::automaton::fixpoint;
 use std::cmp::{max, min};
 use std::collections::{HashMap, HashSet};
 
@@ -133,12 +134,5 @@ pub fn part1(input: &Map) -> usize {
 pub fn part2(input: &Map) -> usize {
     let mut map = input.clone();
     // map.print();
-    for i in 1.. {
-        if !map.evolve() {
-            return i;
-        }
-        // println!("Round {i}");
-        // map.print();
-    }
-    unreachable!();
+    fixpoint(|| map.evolve())
 }
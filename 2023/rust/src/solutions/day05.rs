@@ -8,9 +8,7 @@ use nom::{
     IResult,
 };
 
-use std::cmp::min;
-use std::cmp::Ordering;
-use std::ops::Range;
+use aoc_utils::intervals::{IntervalSet, OffsetMap};
 
 fn unsigned(input: &str) -> IResult<&str, usize> {
     map_res(recognize(digit1), str::parse)(input)
@@ -20,90 +18,11 @@ fn numbers(input: &str) -> IResult<&str, Vec<usize>> {
     delimited(space0, separated_list1(space1, unsigned), space0)(input)
 }
 
-#[derive(Debug, PartialEq, Eq)]
-struct Transformation {
-    range: Range<usize>,
-    offset: isize,
-}
-
-impl Ord for Transformation {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.range.start.cmp(&other.range.start)
-    }
-}
-
-impl PartialOrd for Transformation {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
-impl Transformation {
-    fn get(&self, key: usize) -> usize {
-        ((key as isize) + self.offset) as usize
-    }
-}
-
 #[derive(Debug)]
 pub struct Map<'a> {
     from: &'a str,
     to: &'a str,
-    ranges: Vec<Transformation>,
-}
-
-impl<'a> Map<'a> {
-    fn get_transform(&self, key: usize) -> Result<usize, usize> {
-        self.ranges.binary_search_by(|transform| {
-            if transform.range.contains(&key) {
-                Ordering::Equal
-            } else {
-                transform.range.start.cmp(&key)
-            }
-        })
-    }
-    fn get(&self, key: usize) -> usize {
-        if let Ok(ix) = self.get_transform(key) {
-            self.ranges[ix].get(key)
-        } else {
-            key
-        }
-    }
-
-    fn get_ranges(&self, range: Range<usize>) -> Vec<Range<usize>> {
-        let mut chunks = vec![];
-        let mut range = range;
-        let total_length = range.len();
-        let idx = match self.get_transform(range.start) {
-            Ok(idx) => idx,
-            Err(idx) => idx,
-        };
-
-        for idx in idx..self.ranges.len() {
-            if range.is_empty() {
-                break;
-            }
-            let transform = &self.ranges[idx];
-            if range.start < transform.range.start {
-                let end = min(range.end, transform.range.start);
-                chunks.push(range.start..end);
-                range = end..range.end;
-                if range.is_empty() {
-                    break;
-                }
-            }
-            let start = range.start;
-            let end = min(range.end, transform.range.end);
-            chunks.push(transform.get(start)..transform.get(end));
-            range = end..range.end;
-        }
-
-        if !range.is_empty() {
-            chunks.push(range);
-        }
-        let output_length = chunks.iter().map(|chunk| chunk.len()).sum::<usize>();
-        debug_assert_eq!(output_length, total_length);
-        chunks
-    }
+    offsets: OffsetMap<usize>,
 }
 
 fn parse_map(input: &str) -> IResult<&str, Map<'_>> {
@@ -116,56 +35,46 @@ fn parse_map(input: &str) -> IResult<&str, Map<'_>> {
         separated_list1(newline, tuple((elem(), elem(), elem()))),
         alt((multispace1, eof)),
     )(input)?;
-    let mut ranges: Vec<Transformation> = ranges
-        .into_iter()
-        .map(|(to, from, size)| Transformation {
-            range: from..(from + size),
-            offset: (to as isize - from as isize),
-        })
-        .collect();
-    ranges.sort();
+    let mut offsets = OffsetMap::new();
+    for (to, from, size) in ranges {
+        offsets.insert(from..(from + size), to as i128 - from as i128);
+    }
 
-    Ok((rest, Map { from, to, ranges }))
+    Ok((rest, Map { from, to, offsets }))
 }
 
 #[derive(Debug)]
 pub struct Definition<'a>(Vec<usize>, Vec<Map<'a>>);
 
 impl<'a> Definition<'a> {
-    fn get(&self, key: usize) -> usize {
-        let mut key = key;
-        // We assume maps are in order (see parse)
-        for map in &self.1 {
-            key = map.get(key);
-        }
-        key
+    /// Map from seed to location.
+    /// We assume maps are in order (see parse)
+    fn seed_to_location(&self) -> OffsetMap<usize> {
+        self.1
+            .iter()
+            .fold(OffsetMap::new(), |acc, map| acc.compose(&map.offsets))
     }
 
     fn lowest_location(&self) -> usize {
+        let locations = self.seed_to_location();
         self.0
             .iter()
-            .copied()
-            .map(|seed| self.get(seed))
+            .map(|&seed| locations.apply(seed))
             .min()
             .unwrap()
     }
 
     fn lowest_location2(&self) -> usize {
-        let mut ranges: Vec<Range<usize>> = self
+        let seeds: IntervalSet<usize> = self
             .0
             .chunks(2)
             .map(|chunk| chunk[0]..(chunk[0] + chunk[1]))
             .collect();
-        for map in &self.1 {
-            ranges = ranges
-                .into_iter()
-                .flat_map(|range| map.get_ranges(range))
-                .collect();
-        }
-        ranges
-            .into_iter()
+        self.seed_to_location()
+            .apply_set(&seeds)
+            .spans()
+            .first()
             .map(|range| range.start)
-            .min()
             .expect("no minimum found")
     }
 }
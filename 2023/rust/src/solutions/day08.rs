@@ -1,3 +1,4 @@
+use aoc_utils::numtheory::lcm;
 use std::collections::HashMap;
 
 #[derive(Debug, Clone)]
@@ -77,24 +78,8 @@ pub fn part2(game: &Game) -> usize {
     }
     // dbg!(&cycles, game.directions.len());
     cycles.push(game.directions.len());
-    cycles.into_iter().fold(1, lcm)
-}
-
-fn lcm(first: usize, second: usize) -> usize {
-    first * second / gcd(first, second)
-}
-
-fn gcd(first: usize, second: usize) -> usize {
-    let mut max = first;
-    let mut min = second;
-    if min > max {
-        std::mem::swap(&mut max, &mut min);
-    }
-    loop {
-        let res = max % min;
-        if res == 0 {
-            return min;
-        }
-        std::mem::swap(&mut max, &mut min);
-    }
+    cycles
+        .into_iter()
+        .try_fold(1, lcm)
+        .expect("lcm overflow")
 }
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use aoc_utils::memo::Memo;
 use std::iter::once;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -45,45 +45,35 @@ fn index_by_windows(springs: &[Spring], size: usize) -> impl Iterator<Item = usi
         .map(move |(idx, _)| idx)
 }
 fn resolve(springs: &[Spring], damaged: &[usize]) -> usize {
-    let mut memo = HashMap::<(&[Spring], &[usize]), usize>::new();
-    let mut reused = 0;
-
-    // dbg!(&memo.len(), reused);
-    resolve_memoized(springs, damaged, &mut memo, &mut reused)
+    resolve_memoized(springs, damaged, &mut Memo::new())
 }
 
 fn resolve_memoized<'b>(
     springs: &'b [Spring],
     damaged: &'b [usize],
-    memo: &mut HashMap<(&'b [Spring], &'b [usize]), usize>,
-    counter: &mut usize,
+    memo: &mut Memo<(&'b [Spring], &'b [usize]), usize>,
 ) -> usize {
-    if let Some(sol) = memo.get(&(springs, damaged)) {
-        *counter += 1;
-        return *sol;
-    }
-    let sol = if damaged.is_empty() {
-        if springs.iter().all(|s| s.is_operational()) {
-            1
-        } else {
-            0
+    memo.get((springs, damaged), |memo| {
+        if damaged.is_empty() {
+            return if springs.iter().all(|s| s.is_operational()) {
+                1
+            } else {
+                0
+            };
         }
-    } else {
         let idx_max = damaged.len() / 2;
         let max = damaged[idx_max];
         let (g1, g2) = damaged.split_at(idx_max);
         let g2 = &g2[1..]; // remove idx_max group
         let mut total = 0;
         for (head, tail) in split_by_windows(springs, max) {
-            let r = resolve_memoized(head, g1, memo, counter);
+            let r = resolve_memoized(head, g1, memo);
             if r > 0 {
-                total += r * resolve_memoized(tail, g2, memo, counter);
+                total += r * resolve_memoized(tail, g2, memo);
             }
         }
         total
-    };
-    memo.insert((springs, damaged), sol);
-    sol
+    })
 }
 
 fn surround(springs: &mut Vec<Spring>) {
@@ -1,3 +1,5 @@
+use aoc_utils::polygon::Polygon;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Direction {
     Up,
@@ -6,6 +8,17 @@ enum Direction {
     Right,
 }
 
+impl Direction {
+    fn delta(&self) -> [i128; 2] {
+        match self {
+            Direction::Up => [0, -1],
+            Direction::Down => [0, 1],
+            Direction::Right => [1, 0],
+            Direction::Left => [-1, 0],
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Instruction<'a> {
     direction: Direction,
@@ -56,56 +69,15 @@ pub fn parse(input: &str) -> Vec<Instruction<'_>> {
         .collect()
 }
 
+/// Number of cubic meters in the lagoon, including the trench
 pub fn solve_vertices(instructions: &[Instruction]) -> usize {
-    let mut x: isize = 0;
-    let mut y: isize = 0;
-
-    let mut perimeter: usize = 0;
-
-    let mut vertices = vec![];
-
-    for instruction in instructions {
-        match instruction.direction {
-            Direction::Up => {
-                y -= instruction.count as isize;
-            }
-            Direction::Down => {
-                y += instruction.count as isize;
-            }
-            Direction::Right => {
-                x += instruction.count as isize;
-            }
-            Direction::Left => {
-                x -= instruction.count as isize;
-            }
-        };
-        perimeter += instruction.count;
-        vertices.push((y, x));
-    }
-
-    // Shoelace formula
-    // A = (1/2) * sum((x-x')*(y+y'))
-
-    // Pick's formula:
-    // A = i + b/2 - 1
-    //
-    // 2*A = 2*i + b - 2
-    // 2*A + b = 2*i + 2*b - 2
-    // 2*i+2*b = 2*A + b + 2
-    // i+b = (2*A+b+2)/2
-
-    let twice_area: usize = (vertices
-        .windows(2)
-        .map(|ps| {
-            // acc + ps[0].0*ps[1].1-ps[1].0*ps[0].1
-            (ps[1].1 - ps[0].1) * (ps[1].0 + ps[0].0)
-        })
-        .sum::<isize>()
-        .abs())
-    .try_into()
-    .expect("could not convert area");
-
-    (twice_area + perimeter + 2) / 2
+    let steps = instructions
+        .iter()
+        .map(|instruction| (instruction.direction.delta(), instruction.count as i128));
+    Polygon::from_steps([0, 0], steps)
+        .lattice_points()
+        .try_into()
+        .expect("could not convert area")
 }
 
 pub fn part1(input: &[Instruction]) -> usize {
@@ -1,4 +1,5 @@
-use aoc_utils::lcm;
+use aoc_utils::dbg;
+use aoc_utils::numtheory::crt;
 use nom::{
     branch::alt,
     bytes::complete::tag,
@@ -10,12 +11,9 @@ use nom::{
     IResult,
 };
 
-use std::cell::RefCell;
-use std::rc::Rc;
-
-use std::collections::{BTreeMap, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum Pulse {
+pub enum Pulse {
     High,
     Low,
 }
@@ -27,9 +25,6 @@ enum Gate<'a> {
     Broadcaster,
     FlipFlop(bool),
     Conjunction(BTreeMap<&'a str, Pulse>),
-    /// A Gate that wraps around another Gate and records whenever it receives
-    /// a given type of Pulse.
-    Breaker(Pulse, Rc<RefCell<bool>>, Box<Gate<'a>>),
 }
 
 use Gate::*;
@@ -47,12 +42,6 @@ impl<'a> Gate<'a> {
                 *state = true;
                 Some(High)
             }
-            Breaker(target, ref mut state, ref mut proxy) => {
-                if *target == input {
-                    state.replace(true);
-                }
-                proxy.receive(input, gate)
-            }
             Conjunction(ref mut memory) => {
                 memory.insert(gate, input);
                 if memory.values().all(|p| *p == High) {
@@ -72,41 +61,188 @@ pub struct Circuit<'a> {
     outputs: BTreeMap<&'a str, Vec<&'a str>>,
 }
 
-impl<'a> Circuit<'a> {
-    fn push_button(&mut self) -> (usize, usize, bool) {
-        let mut low_count = 0;
-        let mut high_count = 0;
-        let mut low_rx = false;
+/// A pulse sent from one module to another
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Event<'a> {
+    /// Position of the pulse within its button press
+    pub step: usize,
+    pub from: &'a str,
+    pub to: &'a str,
+    pub pulse: Pulse,
+}
+
+/// A module that feeds the conjunction before the target, and the presses in which it sends a
+/// high pulse: `offset`, `offset + period`, `offset + 2 * period`...
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Feeder<'a> {
+    pub name: &'a str,
+    /// Every module the feeder depends on, except for the broadcaster
+    pub subcircuit: BTreeSet<&'a str>,
+    pub offset: usize,
+    pub period: usize,
+}
 
+impl<'a> Circuit<'a> {
+    /// Press the button once, calling `log` with every pulse that is sent
+    fn press(&mut self, mut log: impl FnMut(Event<'a>)) {
         let mut pulses = VecDeque::from(vec![("button", "broadcaster", Low)]);
-        // println!();
-
-        while let Some((from, name, pulse)) = pulses.pop_front() {
-            // println!("{from} -{pulse:?}-> {name}");
-            if pulse == High {
-                high_count += 1;
-            } else {
-                if name == "rx" {
-                    low_rx = true;
-                }
-                low_count += 1;
-            }
-            let Some(gate) = self.gates.get_mut(name) else {
+        let mut step = 0;
+
+        while let Some((from, to, pulse)) = pulses.pop_front() {
+            log(Event {
+                step,
+                from,
+                to,
+                pulse,
+            });
+            step += 1;
+            let Some(gate) = self.gates.get_mut(to) else {
                 continue;
             };
             let pulse = gate.receive(pulse, from);
             if let Some(pulse) = pulse {
-                if let Some(outs) = self.outputs.get_mut(name) {
+                if let Some(outs) = self.outputs.get(to) {
                     for out in outs {
-                        pulses.push_back((name, *out, pulse));
+                        pulses.push_back((to, *out, pulse));
                     }
                 }
             }
         }
-        (low_count, high_count, low_rx)
+    }
+
+    fn push_button(&mut self) -> (usize, usize) {
+        let mut low_count = 0;
+        let mut high_count = 0;
+        self.press(|event| match event.pulse {
+            High => high_count += 1,
+            Low => low_count += 1,
+        });
+        (low_count, high_count)
+    }
+
+    /// Per-press timeline of the pulses that match `filter`, one item per button press.
+    /// Use `|e| e.from == module` to get the timeline of a module.
+    pub fn trace(
+        &self,
+        filter: impl Fn(&Event<'a>) -> bool,
+    ) -> impl Iterator<Item = Vec<Event<'a>>> {
+        let mut circuit = self.clone();
+        std::iter::repeat_with(move || {
+            let mut events = vec![];
+            circuit.press(|event| {
+                if filter(&event) {
+                    events.push(event);
+                }
+            });
+            events
+        })
+    }
+
+    fn inputs(&self, module: &str) -> Vec<&'a str> {
+        self.outputs
+            .iter()
+            .filter(|(_, outs)| outs.contains(&module))
+            .map(|(name, _)| *name)
+            .collect()
+    }
+
+    /// Find the sub-circuits that drive `target` and the period of each of them.
+    ///
+    /// This expects the target to be fed by a single conjunction, which only sends a low pulse
+    /// when all of its inputs have sent it a high pulse. Each of those inputs (the feeders) has
+    /// to be driven by an independent sub-circuit that sends a high pulse periodically.
+    pub fn feeders(&self, target: &str, max_presses: usize) -> Result<Vec<Feeder<'a>>, String> {
+        let conj = match self.inputs(target)[..] {
+            [conj] => conj,
+            ref other => {
+                return Err(format!(
+                    "{target} should have a single input, but it has {other:?}"
+                ))
+            }
+        };
+        if !matches!(self.gates.get(conj), Some(Conjunction(_))) {
+            return Err(format!(
+                "{conj}, the input of {target}, is not a conjunction"
+            ));
+        }
+        let names = self.inputs(conj);
+
+        let mut feeders: Vec<Feeder> = vec![];
+        for name in names.iter() {
+            let mut subcircuit = BTreeSet::from([*name]);
+            let mut pending = vec![*name];
+            while let Some(module) = pending.pop() {
+                for input in self.inputs(module) {
+                    if input != "broadcaster" && subcircuit.insert(input) {
+                        pending.push(input);
+                    }
+                }
+            }
+            if let Some(other) = feeders
+                .iter()
+                .find(|f| !f.subcircuit.is_disjoint(&subcircuit))
+            {
+                return Err(format!(
+                    "the sub-circuits of {name} and {} are not independent",
+                    other.name
+                ));
+            }
+            feeders.push(Feeder {
+                name,
+                subcircuit,
+                offset: 0,
+                period: 0,
+            });
+        }
+
+        // Presses in which every feeder sends a high pulse to the conjunction
+        let mut highs: BTreeMap<&str, Vec<usize>> = Default::default();
+        let trace = self.trace(|e| e.to == conj && e.pulse == High);
+        for (press, events) in (1..=max_presses).zip(trace) {
+            for event in events {
+                // A feeder may send several high pulses in the same press
+                let presses = highs.entry(event.from).or_default();
+                if presses.last() != Some(&press) {
+                    presses.push(press);
+                }
+            }
+            if names
+                .iter()
+                .all(|n| highs.get(n).map(|h| h.len()).unwrap_or(0) >= SAMPLES)
+            {
+                break;
+            }
+        }
+
+        for feeder in feeders.iter_mut() {
+            let presses = highs.get(feeder.name).cloned().unwrap_or_default();
+            (feeder.offset, feeder.period) = periodicity(&presses)
+                .map_err(|err| format!("{} {err} in {max_presses} presses", feeder.name))?;
+        }
+        Ok(feeders)
     }
 }
 
+/// High pulses needed from every feeder before trusting its period
+const SAMPLES: usize = 5;
+
+/// Offset and period of a sequence of presses, if they are evenly spaced
+fn periodicity(presses: &[usize]) -> Result<(usize, usize), String> {
+    if presses.len() < SAMPLES {
+        return Err(format!(
+            "sent {} high pulses instead of {SAMPLES}",
+            presses.len()
+        ));
+    }
+    let period = presses[1] - presses[0];
+    if !presses.windows(2).all(|w| w[1] - w[0] == period) {
+        return Err(format!(
+            "is not periodic, it sent high pulses in presses {presses:?}"
+        ));
+    }
+    Ok((presses[0], period))
+}
+
 fn line(input: &str) -> IResult<&str, (&str, Gate, Vec<&str>)> {
     let (input, ((gtype, gname), outputs)) = separated_pair(
         tuple((opt(alt((tag("&"), tag("%")))), alpha1)),
@@ -146,71 +282,77 @@ pub fn parse(input: &str) -> Circuit<'_> {
 pub fn part1(input: &Circuit<'_>) -> usize {
     let mut circuit = input.clone();
     let (lc, hc) = (0..1000).fold((0, 0), |acc, _| {
-        let (lc, hc, _) = circuit.push_button();
+        let (lc, hc) = circuit.push_button();
         (acc.0 + lc, acc.1 + hc)
     });
     lc * hc
 }
 
+/// The module that sends a low pulse to rx is a conjunction of several sub-circuits.
+/// Each of them is a counter that sends a high pulse periodically, so the answer is the
+/// first press where all of them coincide.
 pub fn part2(input: &Circuit<'_>) -> usize {
-    let mut circuit = input.clone();
-
-    let mut target = &"rx";
-
-    let watchlist = loop {
-        let mut adjacent = vec![];
-        for (name, outputs) in circuit.outputs.iter() {
-            if outputs.contains(target) {
-                adjacent.push(name);
-            }
-        }
-        match adjacent.len() {
-            0 => panic!("no dependencies found"),
-            1 => {
-                target = adjacent.pop().unwrap();
-            }
-            _a => {
-                break adjacent;
-            }
-        }
-    };
+    let feeders = input
+        .feeders("rx", 100_000)
+        .unwrap_or_else(|err| panic!("unexpected circuit: {err}"));
+    for feeder in &feeders {
+        dbg!(
+            feeder.name,
+            feeder.subcircuit.len(),
+            feeder.offset,
+            feeder.period
+        );
+    }
+    let cycles: Vec<_> = feeders.iter().map(|f| (f.offset, f.period)).collect();
+    let (first, period) =
+        crt(&cycles).expect("the feeders never send a high pulse at the same time");
+    // Presses start at 1, and every feeder needs to have sent its first pulse
+    let min = feeders.iter().map(|f| f.offset).max().unwrap_or(1);
+    if first >= min {
+        first
+    } else {
+        first + (min - first).div_ceil(period) * period
+    }
+}
 
-    let mut signals = Vec::with_capacity(watchlist.len());
+#[cfg(test)]
+mod test {
+    use super::*;
 
-    for name in watchlist.iter() {
-        let original = circuit.gates.remove(*name).expect("unknown gate");
-        let signal = Rc::new(RefCell::new(false));
-        signals.push(signal.clone());
-        let proxy = Breaker(Low, signal, Box::new(original));
-        circuit.gates.insert(name, proxy);
+    #[test]
+    fn test_feeders() {
+        // f sends two high pulses in every press, g sends one every other press
+        let input = &parse(
+            "broadcaster -> f, f, a
+&f -> con
+%a -> g
+&g -> con
+&con -> rx",
+        );
+        let feeders = input.feeders("rx", 100).unwrap();
+        let cycles: Vec<_> = feeders
+            .iter()
+            .map(|f| (f.name, f.offset, f.period))
+            .collect();
+        assert_eq!(cycles, vec![("f", 1, 1), ("g", 2, 2)]);
+        assert_eq!(part2(input), 2);
     }
 
-    let mut cycles = vec![0; watchlist.len()];
+    #[test]
+    fn test_feeders_errors() {
+        let input = &parse("broadcaster -> a\n%a -> rx");
+        assert!(input.feeders("rx", 100).is_err());
+        // a only gets high pulses, so it never sends anything
+        let input = &parse("broadcaster -> c\n&c -> a\n%a -> con\n&con -> rx");
+        assert!(input.feeders("rx", 100).is_err());
+    }
 
-    for times in 1.. {
-        circuit.push_button();
-        for (ix, signal) in signals.iter().enumerate() {
-            if *signal.as_ref().borrow() && cycles[ix] == 0 && times > 1 {
-                cycles[ix] = times;
-            }
-        }
-        if cycles.iter().all(|i| *i > 0) {
-            break;
-        }
+    #[test]
+    fn test_periodicity() {
+        assert_eq!(periodicity(&[3, 5, 7, 9, 11]), Ok((3, 2)));
+        assert_eq!(periodicity(&[4, 8, 12, 16, 20, 24]), Ok((4, 4)));
+        // Would pass a check against the first press, but 7 is missing
+        assert!(periodicity(&[3, 5, 9, 11, 13]).is_err());
+        assert!(periodicity(&[3, 5, 7]).is_err());
     }
-    cycles.into_iter().fold(1, lcm)
-
-    // As an alternative:
-    //
-    // Generate network to visually analyze the graph.
-    //
-    // let filtered = ["broadcaster", "kt", "mt", "zp", "rc", "hj", "vc", "jv", "hf", "nm", "dh", "mc", "lv", "tg", "output"];
-    // circuit.outputs.retain(|k, v| filtered.contains(k));
-    // for (i, outs) in circuit.outputs.iter() {
-    //     for out in outs {
-    //         println!("{i}>{out}");
-    //     }
-    // }
-    // The graph shows 4 distinct structures that
-    // 243221023462303
 }
@@ -1,71 +1,9 @@
+use aoc_utils::cuboid::Cuboid;
 use std::collections::{BTreeMap, BTreeSet};
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct Point {
-    x: usize,
-    y: usize,
-    z: usize,
-}
-
-#[derive(Clone, Debug, Hash, PartialOrd, Ord, PartialEq, Eq)]
-pub struct Brick {
-    points: BTreeMap<(usize, usize), Vec<usize>>,
-    bottom: usize,
-    top: usize,
-}
-
-impl Brick {
-    fn new(c1: Point, c2: Point) -> Self {
-        let y0 = c1.y.min(c2.y);
-        let y1 = c1.y.max(c2.y);
-        let x0 = c1.x.min(c2.x);
-        let x1 = c1.x.max(c2.x);
-        let z0 = c1.z.min(c2.z);
-        let z1 = c1.z.max(c2.z);
-
-        let mut points: BTreeMap<(usize, usize), Vec<usize>> = BTreeMap::new();
-        for y in y0..=y1 {
-            for x in x0..=x1 {
-                for z in z0..=z1 {
-                    points.entry((x, y)).or_default().push(z);
-                }
-            }
-        }
-        Brick {
-            points,
-            bottom: z0,
-            top: z1,
-        }
-    }
+type Brick = Cuboid<3>;
 
-    fn fall(&mut self, height: usize) {
-        self.bottom -= height;
-        self.top -= height;
-        for v in self.points.values_mut() {
-            for z in v.iter_mut() {
-                *z -= height;
-            }
-        }
-    }
-
-    fn z(&self) -> usize {
-        self.bottom
-    }
-
-    fn distance(&self, other: &Self) -> Option<usize> {
-        self.points
-            .iter()
-            .filter_map(|(xy, zs)| {
-                let myz = &zs[0];
-                other
-                    .points
-                    .get(xy)
-                    .and_then(|ozs| ozs.iter().filter(|&oz| oz < myz).last().map(|oz| myz - oz))
-            })
-            .min()
-            .map(|m| m - 1)
-    }
-}
+const DOWN: [isize; 3] = [0, 0, -1];
 
 #[derive(Debug, Clone)]
 pub struct Problem {
@@ -76,22 +14,19 @@ pub struct Problem {
 impl Problem {
     fn new(bricks: &[Brick]) -> Self {
         let mut bricks: Vec<_> = bricks.into();
-        bricks.sort_by(|a, b| a.top.cmp(&b.top));
-        let bricks = bricks.into_iter().fold(vec![], |mut acc, b| {
-            let b = make_fall(&b, &acc);
-            let pos = acc
-                .binary_search_by_key(&b.top, |b| b.top)
-                .unwrap_or_else(|e| e);
-            acc.insert(pos, b);
-            acc
-        });
+        bricks.sort_by_key(|b| b.min[2]);
+        let mut fallen = vec![];
+        for b in bricks {
+            fallen.push(make_fall(&b, &fallen));
+        }
+        let mut bricks = fallen;
+        // Any brick that supports another one is lower, so it goes first
+        bricks.sort_by_key(|b| b.max[2]);
         let mut dependencies: BTreeMap<usize, BTreeSet<usize>> = Default::default();
         for (ix, bi) in bricks.iter().enumerate() {
+            let below = bi.translate(DOWN);
             for (jx, bj) in bricks.iter().enumerate() {
-                if ix == jx {
-                    continue;
-                }
-                if bi.distance(bj) == Some(0) {
+                if ix != jx && below.overlaps(bj) {
                     dependencies.entry(ix).or_default().insert(jx);
                 }
             }
@@ -103,22 +38,18 @@ impl Problem {
     }
 }
 
+/// Move a brick down until it rests on the floor (z = 1) or on a brick that has already fallen
 fn make_fall(brick: &Brick, fallen: &[Brick]) -> Brick {
-    let mut max_drop: Option<usize> = None;
-
-    for other in fallen.iter().rev() {
-        if let Some(thisdrop) = brick.distance(other) {
-            if let Some(m) = max_drop {
-                max_drop = Some(m.min(thisdrop));
-            } else {
-                max_drop = Some(thisdrop);
-            }
-            break;
-        }
-    }
-    let mut result = brick.clone();
-    result.fall(max_drop.unwrap_or_else(|| brick.z() - 1));
-    result
+    let mut shadow = *brick;
+    shadow.max[2] = brick.min[2];
+    shadow.min[2] = 1;
+    let floor = fallen
+        .iter()
+        .filter(|other| other.overlaps(&shadow))
+        .map(|other| other.max[2])
+        .max()
+        .unwrap_or(1);
+    brick.translate([0, 0, floor - brick.min[2]])
 }
 
 pub fn parse(input: &str) -> Problem {
@@ -128,21 +59,17 @@ pub fn parse(input: &str) -> Problem {
         .filter(|line| !line.is_empty())
         .map(|line| {
             let mut points = line.split('~').map(|coords| {
-                let nums: Vec<usize> = coords
+                let nums: Vec<isize> = coords
                     .split(',')
                     .map(|number| {
                         number
-                            .parse::<usize>()
+                            .parse::<isize>()
                             .unwrap_or_else(|_| panic!("invalid number {number}"))
                     })
                     .collect();
-                Point {
-                    x: nums[0],
-                    y: nums[1],
-                    z: nums[2],
-                }
+                [nums[0], nums[1], nums[2]]
             });
-            Brick::new(points.next().unwrap(), points.next().unwrap())
+            Cuboid::from_corners(points.next().unwrap(), points.next().unwrap())
         })
         .collect();
     Problem::new(&bricks)
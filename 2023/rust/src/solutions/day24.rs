@@ -1,66 +1,69 @@
-use glam::DVec3;
+use aoc_utils::linalg::{rational, to_integers, Matrix, Rational};
 use std::fmt;
 
-type Position = DVec3;
-type Velocity = Position;
+type Vec3 = [i128; 3];
+
+fn sub(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+/// Matrix of the cross product with `a`: `skew(a) * b == a x b`
+fn skew(a: Vec3) -> [Vec3; 3] {
+    [[0, -a[2], a[1]], [a[2], 0, -a[0]], [-a[1], a[0], 0]]
+}
 
 #[derive(Clone, Copy, PartialEq)]
 struct Stone {
-    pos: Position,
-    vel: Velocity,
-    a: f64,
-    b: f64,
+    pos: Vec3,
+    vel: Vec3,
 }
 
 impl fmt::Debug for Stone {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} @ {}", self.pos, self.vel)
+        write!(f, "{:?} @ {:?}", self.pos, self.vel)
     }
 }
 
 impl Stone {
     fn from_str(input: &str) -> Self {
-        let tokens: Vec<f64> = input
+        let tokens: Vec<i128> = input
             .split(&[',', '@'])
-            .map(|t| t.trim().parse::<f64>().expect("invalid number"))
+            .map(|t| t.trim().parse().expect("invalid number"))
             .collect();
-        let pos = [tokens[0], tokens[1], tokens[2]];
-        let vel = [tokens[3], tokens[4], tokens[5]];
-        Self::new(pos.into(), vel.into())
-    }
-    fn new(pos: Position, vel: Velocity) -> Self {
-        let a = vel[1] / vel[0];
-        let b = pos[1] - pos[0] * (vel[1] / vel[0]);
-
-        Self { vel, pos, a, b }
+        Self {
+            pos: [tokens[0], tokens[1], tokens[2]],
+            vel: [tokens[3], tokens[4], tokens[5]],
+        }
     }
 
-    fn collision_at_xy(&self, other: &Self) -> Option<[f64; 2]> {
-        let (a, b, c, d) = { (self.a, other.a, self.b, other.b) };
-
-        if a == b {
-            if c == d {
-                panic!("same line!");
-            } else {
-                return None;
-            }
+    /// Where the paths of both stones cross (ignoring the z axis), if they do so in the future.
+    /// The coordinates are fractions `([x, y], denominator)`, so they are exact.
+    fn collision_at_xy(&self, other: &Self) -> Option<([i128; 2], i128)> {
+        // self.pos + self.vel * t == other.pos + other.vel * s, solved with Cramer's rule
+        let (a, b) = (self.vel, other.vel);
+        let delta = sub(other.pos, self.pos);
+        let mut det = b[0] * a[1] - a[0] * b[1];
+        if det == 0 {
+            return None;
         }
-
-        let x = (d - c) / (a - b);
-        let y1 = a * x + c;
-        let y2 = b * x + d;
-
-        if self.is_past_at_xy([x, y1]) || other.is_past_at_xy([x, y2]) {
+        let mut t = b[0] * delta[1] - delta[0] * b[1];
+        let mut s = a[0] * delta[1] - delta[0] * a[1];
+        if det < 0 {
+            (det, t, s) = (-det, -t, -s);
+        }
+        if t < 0 || s < 0 {
             //eprintln!("ïn the past");
-            None
-        } else {
-            Some([x, y1])
+            return None;
         }
-    }
-
-    fn is_past_at_xy(&self, pos: [f64; 2]) -> bool {
-        (((pos[0] - self.pos.x) < 0.0) ^ (self.vel.x < 0.0))
-            || (((pos[1] - self.pos.y) < 0.0) ^ (self.vel.y < 0.0))
+        Some(([0, 1].map(|axis| self.pos[axis] * det + a[axis] * t), det))
     }
 }
 
@@ -79,13 +82,14 @@ pub fn parse(input: &str) -> Hail {
     }
 }
 
-fn solve(hail: &Hail, min: f64, max: f64) -> usize {
+fn solve(hail: &Hail, min: i128, max: i128) -> usize {
     let mut count = 0;
     for (ix, stone) in hail.stones.iter().enumerate() {
         for other in hail.stones.iter().take(ix) {
-            if let Some([y, x]) = stone.collision_at_xy(other) {
-                //dbg!(stone, other, [y, x]);
-                if x >= min && x <= max && y >= min && y <= max {
+            if let Some(([x, y], det)) = stone.collision_at_xy(other) {
+                //dbg!(stone, other, [x, y]);
+                let range = min * det..=max * det;
+                if range.contains(&x) && range.contains(&y) {
                     //eprintln!("Valid");
                     count += 1;
                 }
@@ -97,41 +101,51 @@ fn solve(hail: &Hail, min: f64, max: f64) -> usize {
 
 pub fn part1(input: &Hail) -> usize {
     if input.stones.len() > 10 {
-        solve(input, 200000000000000f64, 400000000000000f64)
+        solve(input, 200000000000000, 400000000000000)
     } else {
-        solve(input, 7f64, 72f64)
+        solve(input, 7, 72)
     }
 }
 
+/// Position and velocity of a rock that hits every stone, using three of them
+fn throw(stones: &[Stone]) -> Option<Vec<Rational>> {
+    // The rock (P, V) and each stone (p, v) meet, so (P - p) x (V - v) == 0, or:
+    //   P x V - P x v - p x V + p x v == 0
+    // P x V is the same for every stone, so subtracting the equations of two stones gives
+    // linear equations:
+    //   P x (v1 - v0) + (p1 - p0) x V == p1 x v1 - p0 x v0
+    let [s0, rest @ ..] = stones else {
+        return None;
+    };
+    let mut rows = vec![];
+    let mut rhs = vec![];
+    for s in rest {
+        let w = skew(sub(s.vel, s0.vel));
+        let d = skew(sub(s.pos, s0.pos));
+        let target = sub(cross(s.pos, s.vel), cross(s0.pos, s0.vel));
+        for axis in 0..3 {
+            // P x w == -(w x P)
+            rows.push(
+                w[axis]
+                    .iter()
+                    .map(|c| -c)
+                    .chain(d[axis].iter().copied())
+                    .collect::<Vec<i128>>(),
+            );
+            rhs.push(rational(target[axis]));
+        }
+    }
+    Matrix::from_rows(rows).solve(&rhs).unique()
+}
+
 pub fn part2(input: &Hail) -> usize {
-    // It has to collide with every hailstone,
-    // so we can pick one at random as our reference.
-    let origin = input.stones[0];
-    // Then we convert the coordinate system to use our origin
-    // hailstone as origin.
-    let rotated: Vec<Stone> = input
+    // Three stones are usually enough, unless some of their paths are parallel
+    let rock = input
         .stones
-        .iter()
-        .skip(1)
-        // We need at least two more points to define the trajectory
-        .take(2)
-        .map(|s| Stone::new(s.pos - origin.pos, s.vel - origin.vel))
-        .collect();
-
-    // We need to find out the points where the other two
-    // hailstones are aligned with the center
-    // At those points, the vectors p1 and p2 are aligned (zero doc product)
-    let p1 = rotated[0].pos;
-    let v1 = rotated[0].vel;
-    let p2 = rotated[1].pos;
-    let v2 = rotated[1].vel;
-    let t1 = -p1.cross(p2).dot(v2) / (v1.cross(p2).dot(v2));
-    let t2 = -p1.cross(p2).dot(v1) / (p1.cross(v2).dot(v1));
-    let c1 = p1 + t1 * v1;
-    let c2 = p2 + t2 * v2;
-
-    let v = (c2 - c1) / (t2 - t1);
-    let p = (c1 - t1 * v) + origin.pos;
-    //dbg!(p);
-    p.element_sum().ceil() as usize
+        .windows(3)
+        .find_map(throw)
+        .expect("no rock hits every stone");
+    let rock = to_integers(&rock).expect("the rock does not start at integer coordinates");
+    //dbg!(&rock);
+    rock[..3].iter().sum::<i128>() as usize
 }
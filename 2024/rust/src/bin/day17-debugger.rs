@@ -0,0 +1,27 @@
+//! Step debugger for the day 17 computer. See `day17::debugger::Debugger` for the commands.
+//!
+//! Commands are read from the script file if one is given, or interactively from stdin.
+use aoc2024::solutions::day17;
+use aoc_utils::{arg, Command};
+use std::fs::{read_to_string, File};
+use std::io::{self, BufReader};
+
+fn main() {
+    let args = Command::new("day17-debugger")
+        .about("Step debugger for the AoC 2024 day 17 computer")
+        .arg(arg!(<input> "Input file with the registers and the program"))
+        .arg(arg!([script] "File with debugger commands (read from stdin if missing)"))
+        .get_matches();
+
+    let input = args.value_of("input").expect("the input is required");
+    let input = read_to_string(input).expect("could not read input file");
+    let mut dbg = day17::debugger::Debugger::new(&day17::parse(&input));
+    let mut stdout = io::stdout();
+    match args.value_of("script") {
+        Some(script) => {
+            let file = File::open(script).expect("could not open debugger script");
+            dbg.run(BufReader::new(file), &mut stdout);
+        }
+        None => dbg.run(io::stdin().lock(), &mut stdout),
+    }
+}
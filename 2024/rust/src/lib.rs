@@ -0,0 +1,2 @@
+//! The solutions as a library, so the binaries in `src/bin` can reuse them.
+pub mod solutions;
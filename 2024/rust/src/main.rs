@@ -1,5 +1,3 @@
-mod solutions;
-
 fn main() {
-    solutions::main();
+    aoc2024::solutions::main();
 }
@@ -1,3 +1,4 @@
+use aoc_utils::unionfind::UnionFind;
 use std::collections::HashMap;
 
 type Input = Vec<Vec<char>>;
@@ -9,40 +10,28 @@ pub fn parse(i: &str) -> Input {
         .collect()
 }
 
+/// Label every plot with its region, and get the area of each region
 fn regions_area(input: &Input) -> (Vec<Vec<usize>>, HashMap<usize, usize>) {
-    let mut regions: Vec<Vec<usize>> = vec![vec![0; input[0].len()]; input.len()];
-    let mut area: HashMap<usize, usize> = Default::default();
-    let mut nregions = 0;
+    let width = input[0].len();
+    let mut plots = UnionFind::new(input.len() * width);
     for (i, row) in input.iter().enumerate() {
         for (j, target) in row.iter().enumerate() {
-            if regions[i][j] != 0 {
-                continue;
+            if j + 1 < width && row[j + 1] == *target {
+                plots.union(i * width + j, i * width + j + 1);
             }
-            nregions += 1;
-            let mut pending = vec![(i, j)];
-            while let Some(nxt) = pending.pop() {
-                let (ni, nj) = nxt;
-                if regions[ni][nj] != 0 {
-                    continue;
-                }
-                area.entry(nregions).and_modify(|v| *v += 1).or_insert(1);
-                regions[ni][nj] = nregions;
-                for (di, dj) in [(-1, 0), (1, 0), (0, 1), (0, -1)] {
-                    let (pi, underi) = ni.overflowing_add_signed(di);
-                    let (pj, underj) = nj.overflowing_add_signed(dj);
-                    if underi
-                        || underj
-                        || pi >= input.len()
-                        || pj >= input[pi].len()
-                        || input[pi][pj] != *target
-                    {
-                        continue;
-                    }
-                    pending.push((pi, pj));
-                }
+            if i + 1 < input.len() && input[i + 1][j] == *target {
+                plots.union(i * width + j, (i + 1) * width + j);
             }
         }
     }
+    let regions: Vec<Vec<usize>> = (0..input.len())
+        .map(|i| (0..width).map(|j| plots.find(i * width + j)).collect())
+        .collect();
+    let area = regions
+        .iter()
+        .flatten()
+        .map(|&region| (region, plots.size(region)))
+        .collect();
     (regions, area)
 }
 
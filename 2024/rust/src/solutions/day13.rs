@@ -8,9 +8,9 @@ use nom::sequence::{pair, preceded, separated_pair, terminated};
 use nom::IResult;
 use nom::Parser;
 
-use nalgebra::{vector, Affine2, Matrix2, Point2, Vector2};
+use aoc_utils::linalg::{rational, to_integers, Matrix};
 
-type Pos = Vector2<f64>;
+type Pos = [i64; 2];
 
 #[derive(Debug, Clone)]
 struct Button {
@@ -26,38 +26,32 @@ pub struct Machine {
 }
 
 impl Machine {
+    /// Tokens needed to win the prize, if it can be reached
     fn pushes2buttons(&self) -> Option<usize> {
-        let b0 = self.buttons[0].coord;
-        let b1 = self.buttons[1].coord;
-        let mat = Affine2::<f64>::from_matrix_unchecked(
-            Matrix2::new(b0.x, b1.x, b0.y, b1.y).to_homogeneous(),
-        );
-        let inv = mat.try_inverse().unwrap();
-        let res = inv.transform_point(&(Point2::origin() + self.prize));
-        let t0 = res.coords.x.round();
-        let t1 = res.coords.y.round();
-
-        if (t0 - res.coords.x).abs() > 0.001f64 {
-            return None;
-        }
-        if (t1 - res.coords.y).abs() > 0.001f64 {
-            return None;
+        let [b0, b1] = [self.buttons[0].coord, self.buttons[1].coord];
+        let mat = Matrix::from_rows([[b0[0], b1[0]], [b0[1], b1[1]]]);
+        let pushes = mat
+            .solve(&self.prize.map(rational))
+            .unique()
+            .and_then(|res| to_integers(&res))?;
+        match pushes[..] {
+            [t0, t1] if t0 >= 0 && t1 >= 0 => Some(3 * t0 as usize + t1 as usize),
+            _ => None,
         }
-        Some(3 * (t0 as usize) + t1 as usize)
     }
 }
 
 fn deltacoord(i: &str) -> IResult<&str, Pos> {
     let (i, pos) = alt((
-        preceded(tag("X=").or(tag("X")), ni64).map(|x| Vector2::new(x as f64, 0f64)),
-        preceded(tag("Y=").or(tag("Y")), ni64).map(|y| Vector2::new(0f64, y as f64)),
+        preceded(tag("X=").or(tag("X")), ni64).map(|x| [x, 0]),
+        preceded(tag("Y=").or(tag("Y")), ni64).map(|y| [0, y]),
     ))(i)?;
     Ok((i, pos))
 }
 
 fn parse_coord(i: &str) -> IResult<&str, Pos> {
     let (i, (d1, d2)) = separated_pair(deltacoord, tag(", "), deltacoord)(i)?;
-    Ok((i, d1 + d2))
+    Ok((i, [d1[0] + d2[0], d1[1] + d2[1]]))
 }
 
 fn parse_machine(i: &str) -> IResult<&str, Machine> {
@@ -91,12 +85,12 @@ pub fn part1(i: &[Machine]) -> usize {
 }
 
 pub fn part2(i: &[Machine]) -> usize {
-    let offset = vector![10000000000000f64, 10000000000000f64];
+    let offset = 10000000000000;
     let newi: Vec<_> = i
         .iter()
         .map(move |m| {
             let mut m = m.clone();
-            m.prize += offset;
+            m.prize = m.prize.map(|c| c + offset);
             m
         })
         .collect();
@@ -45,7 +45,7 @@ impl Computer {
         self.program[pos].0 as i64
     }
 
-    fn execute(&mut self) -> Result<Option<String>, ()> {
+    fn execute(&mut self) -> Result<Option<i64>, ()> {
         let pos = self.ptr;
         if pos >= self.program.len() - 1 {
             return Err(());
@@ -85,8 +85,7 @@ impl Computer {
             }
             //out
             5 => {
-                let val = self.combo(pos + 1) % 8;
-                out = Some(format!("{}", val));
+                out = Some(self.combo(pos + 1) % 8);
             }
             // bdv
             6 => {
@@ -116,7 +115,6 @@ impl Computer {
         Ok(out)
     }
 }
-
 pub fn computer(mut i: &str) -> IResult<&str, Computer> {
     let reg = separated_pair(
         preceded(tag("Register "), alt((tag("A"), tag("B"), tag("C")))),
@@ -163,17 +161,21 @@ pub fn parse(i: &str) -> Computer {
     c
 }
 
-pub fn part1(i: &Computer) -> String {
+fn run(i: &Computer) -> String {
     let mut pc = i.clone();
     let mut outs = vec![];
     while let Ok(opt) = pc.execute() {
         if let Some(out) = opt {
-            outs.push(out);
+            outs.push(out.to_string());
         }
     }
     outs.join(",")
 }
 
+pub fn part1(i: &Computer) -> String {
+    run(i)
+}
+
 /// I did some manual analysis of the code, and MY program is a loop
 /// that updates the B and C registers based on the previous value of A
 /// and sets A to A / 8.
@@ -199,7 +201,7 @@ pub fn part2(pc: &Computer) -> u64 {
 
     let mut nc = pc.clone();
     nc.regs.a = sol as i64;
-    let out = part1(&nc);
+    let out = run(&nc);
     let expected = pc
         .program
         .iter()
@@ -209,3 +211,397 @@ pub fn part2(pc: &Computer) -> u64 {
     assert_eq!(expected, out);
     sol
 }
+
+/// Step debugger and disassembler, used by the `day17-debugger` binary.
+pub mod debugger {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Opcode {
+        Adv,
+        Bxl,
+        Bst,
+        Jnz,
+        Bxc,
+        Out,
+        Bdv,
+        Cdv,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct Instruction {
+        op: Opcode,
+        operand: u64,
+    }
+
+    impl Instruction {
+        fn combo(&self) -> &'static str {
+            match self.operand {
+                0 => "0",
+                1 => "1",
+                2 => "2",
+                3 => "3",
+                4 => "A",
+                5 => "B",
+                6 => "C",
+                _ => "<reserved>",
+            }
+        }
+
+        /// Pseudo-code equivalent of the instruction, with combo operands resolved
+        fn describe(&self) -> String {
+            let (combo, lit) = (self.combo(), self.operand);
+            match self.op {
+                Opcode::Adv => format!("A = A >> {combo}"),
+                Opcode::Bxl => format!("B = B ^ {lit}"),
+                Opcode::Bst => format!("B = {combo} % 8"),
+                Opcode::Jnz => format!("if A != 0: goto {lit}"),
+                Opcode::Bxc => "B = B ^ C".into(),
+                Opcode::Out => format!("out {combo} % 8"),
+                Opcode::Bdv => format!("B = A >> {combo}"),
+                Opcode::Cdv => format!("C = A >> {combo}"),
+            }
+        }
+    }
+
+    impl std::fmt::Display for Instruction {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            let name = format!("{:?}", self.op).to_lowercase();
+            match self.op {
+                Opcode::Bxl | Opcode::Jnz => write!(f, "{name} {}", self.operand),
+                Opcode::Bxc => write!(f, "{name}"),
+                _ => write!(f, "{name} {}", self.combo()),
+            }
+        }
+    }
+
+    impl Computer {
+        fn decode(&self, pos: usize) -> Option<Instruction> {
+            let op = match self.program.get(pos)?.0 {
+                0 => Opcode::Adv,
+                1 => Opcode::Bxl,
+                2 => Opcode::Bst,
+                3 => Opcode::Jnz,
+                4 => Opcode::Bxc,
+                5 => Opcode::Out,
+                6 => Opcode::Bdv,
+                7 => Opcode::Cdv,
+                _ => return None,
+            };
+            let operand = self.program.get(pos + 1)?.0;
+            Some(Instruction { op, operand })
+        }
+
+        fn disassemble(&self) -> Vec<String> {
+            (0..self.program.len())
+                .step_by(2)
+                .filter_map(|pos| {
+                    let inst = self.decode(pos)?;
+                    let marker = if pos == self.ptr { "=>" } else { "  " };
+                    let name = inst.to_string();
+                    Some(format!(
+                        "{marker} {pos:>3}: {name:<8} ; {}",
+                        inst.describe()
+                    ))
+                })
+                .collect()
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Watch {
+        A,
+        B,
+        C,
+        Output,
+    }
+
+    /// Instructions that `continue` executes before giving up, unless told otherwise
+    pub const MAX_STEPS: usize = 1_000_000;
+
+    /// Step debugger for the computer.
+    ///
+    /// It reads one command per line (from a script or from stdin):
+    ///
+    /// * `break <ip>` / `delete <ip>`: add or remove a breakpoint on the instruction pointer
+    /// * `watch <a|b|c|out>` / `unwatch <a|b|c|out>`: stop when a register or the output changes
+    /// * `trace <on|off>`: log every executed instruction with the registers before and after
+    /// * `step [n]`, `continue [max]`: execute `n` instructions, or until a breakpoint, watch or
+    ///   halt (giving up after `max` instructions, `MAX_STEPS` by default)
+    /// * `set <a|b|c> <value>`, `reset`: modify the registers, or go back to the initial state
+    /// * `regs`, `out`, `disasm`: inspect the state, the output so far and the program
+    pub struct Debugger {
+        initial: Computer,
+        pc: Computer,
+        output: Vec<i64>,
+        breakpoints: Vec<usize>,
+        watches: Vec<Watch>,
+        trace: bool,
+        halted: bool,
+    }
+
+    impl Debugger {
+        pub fn new(pc: &Computer) -> Self {
+            Debugger {
+                initial: pc.clone(),
+                pc: pc.clone(),
+                output: vec![],
+                breakpoints: vec![],
+                watches: vec![],
+                trace: false,
+                halted: false,
+            }
+        }
+
+        fn regs(&self) -> String {
+            let Registers { a, b, c } = self.pc.regs;
+            format!("ip={:<3} A={a} B={b} C={c}", self.pc.ptr)
+        }
+
+        fn watched(&self, w: Watch) -> i64 {
+            match w {
+                Watch::A => self.pc.regs.a,
+                Watch::B => self.pc.regs.b,
+                Watch::C => self.pc.regs.c,
+                Watch::Output => self.output.len() as i64,
+            }
+        }
+
+        /// Execute a single instruction, returning the reason to stop (if any)
+        fn step(&mut self, log: &mut impl std::io::Write) -> Option<String> {
+            if self.halted {
+                return Some("program halted".into());
+            }
+            let before: Vec<i64> = self.watches.iter().map(|&w| self.watched(w)).collect();
+            let (ptr, regs) = (self.pc.ptr, self.regs());
+            let inst = self.pc.decode(ptr);
+            match self.pc.execute() {
+                Ok(out) => self.output.extend(out),
+                Err(()) => {
+                    self.halted = true;
+                    return Some("program halted".into());
+                }
+            }
+            if self.trace {
+                let inst = inst.map(|i| i.to_string()).unwrap_or_default();
+                writeln!(log, "{ptr:>3}: {inst:<8} | {regs} -> {}", self.regs()).unwrap();
+            }
+            let changed: Vec<String> = self
+                .watches
+                .iter()
+                .zip(before)
+                .filter(|&(&w, old)| self.watched(w) != old)
+                .map(|(&w, old)| match w {
+                    Watch::Output => format!("output is now {:?}", self.output),
+                    _ => format!("{w:?} changed from {old} to {}", self.watched(w)),
+                })
+                .collect();
+            if !changed.is_empty() {
+                return Some(format!("watchpoint: {}", changed.join(", ")));
+            }
+            if self.breakpoints.contains(&self.pc.ptr) {
+                return Some(format!("breakpoint at {}", self.pc.ptr));
+            }
+            None
+        }
+
+        /// Run a single command, writing its result to `log`.
+        /// Returns false if the debugger should exit.
+        pub fn command(&mut self, line: &str, log: &mut impl std::io::Write) -> bool {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let watch = |w: &str| match w {
+                "a" | "A" => Some(Watch::A),
+                "b" | "B" => Some(Watch::B),
+                "c" | "C" => Some(Watch::C),
+                "out" => Some(Watch::Output),
+                _ => None,
+            };
+            let msg = match words[..] {
+                [] => return true,
+                ["quit" | "q"] => return false,
+                ["break" | "b", ip] => match ip.parse() {
+                    Ok(ip) => {
+                        self.breakpoints.push(ip);
+                        format!("breakpoint set at {ip}")
+                    }
+                    Err(_) => format!("invalid ip {ip}"),
+                },
+                ["delete", ip] => match ip.parse() {
+                    Ok(ip) if self.breakpoints.contains(&ip) => {
+                        self.breakpoints.retain(|&b| b != ip);
+                        format!("breakpoint removed at {ip}")
+                    }
+                    Ok(ip) => format!("no breakpoint at {ip}"),
+                    Err(_) => format!("invalid ip {ip}"),
+                },
+                ["watch", w] => match watch(w) {
+                    Some(w) => {
+                        self.watches.push(w);
+                        format!("watching {w:?}")
+                    }
+                    None => format!("cannot watch {w}"),
+                },
+                ["unwatch", w] => {
+                    self.watches.retain(|&o| Some(o) != watch(w));
+                    format!("not watching {w}")
+                }
+                ["trace", "on"] => {
+                    self.trace = true;
+                    "trace enabled".into()
+                }
+                ["trace", "off"] => {
+                    self.trace = false;
+                    "trace disabled".into()
+                }
+                ["step" | "s", ref n @ ..] if n.len() <= 1 => {
+                    let n: usize = n.first().and_then(|n| n.parse().ok()).unwrap_or(1);
+                    let reason = (0..n).find_map(|_| self.step(log));
+                    format!(
+                        "{}{}",
+                        reason.map(|r| r + "\n").unwrap_or_default(),
+                        self.regs()
+                    )
+                }
+                ["continue" | "c", ref n @ ..] if n.len() <= 1 => {
+                    let max = n.first().and_then(|n| n.parse().ok()).unwrap_or(MAX_STEPS);
+                    let reason = (0..max).find_map(|_| self.step(log));
+                    let reason = reason.unwrap_or_else(|| format!("step limit reached ({max})"));
+                    format!("{reason}\n{}", self.regs())
+                }
+                ["set", reg, value] => match (watch(reg), value.parse()) {
+                    (Some(Watch::A), Ok(v)) => {
+                        self.pc.regs.a = v;
+                        self.regs()
+                    }
+                    (Some(Watch::B), Ok(v)) => {
+                        self.pc.regs.b = v;
+                        self.regs()
+                    }
+                    (Some(Watch::C), Ok(v)) => {
+                        self.pc.regs.c = v;
+                        self.regs()
+                    }
+                    _ => format!("cannot set {reg} to {value}"),
+                },
+                ["reset"] => {
+                    self.pc = self.initial.clone();
+                    self.output.clear();
+                    self.halted = false;
+                    self.regs()
+                }
+                ["regs" | "r"] => self.regs(),
+                ["out"] => format!("{:?}", self.output),
+                ["disasm"] => self.pc.disassemble().join("\n"),
+                _ => format!("unknown command: {line}"),
+            };
+            writeln!(log, "{msg}").unwrap();
+            true
+        }
+
+        /// Run commands from `input` until it is exhausted or the user quits.
+        pub fn run(&mut self, input: impl std::io::BufRead, log: &mut impl std::io::Write) {
+            for line in input.lines() {
+                let line = line.expect("could not read command");
+                if !self.command(&line, log) {
+                    break;
+                }
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        const EXAMPLE: &str = "Register A: 729
+Register B: 0
+Register C: 0
+
+Program: 0,1,5,4,3,0
+";
+
+        fn session(script: &str) -> String {
+            let mut log = vec![];
+            Debugger::new(&parse(EXAMPLE)).run(script.as_bytes(), &mut log);
+            String::from_utf8(log).unwrap()
+        }
+
+        #[test]
+        fn test_breakpoints() {
+            let log = session("break 4\ncontinue\ndelete 4\ndelete 4\ndelete x\ncontinue\nout");
+            let lines: Vec<&str> = log.lines().collect();
+            assert_eq!(
+                lines,
+                vec![
+                    "breakpoint set at 4",
+                    "breakpoint at 4",
+                    "ip=4   A=364 B=0 C=0",
+                    "breakpoint removed at 4",
+                    "no breakpoint at 4",
+                    "invalid ip x",
+                    "program halted",
+                    "ip=6   A=0 B=0 C=0",
+                    "[4, 6, 3, 5, 6, 3, 5, 2, 1, 0]",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_watchpoints() {
+            let log = session("watch out\ncontinue\nunwatch out\nwatch a\ncontinue\nwatch x");
+            let lines: Vec<&str> = log.lines().collect();
+            assert_eq!(
+                lines,
+                vec![
+                    "watching Output",
+                    "watchpoint: output is now [4]",
+                    "ip=4   A=364 B=0 C=0",
+                    "not watching out",
+                    "watching A",
+                    "watchpoint: A changed from 364 to 182",
+                    "ip=2   A=182 B=0 C=0",
+                    "cannot watch x",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_step_limit() {
+            // jnz 0 loops forever while A is not zero
+            let pc = parse("Register A: 1\nRegister B: 0\nRegister C: 0\n\nProgram: 3,0\n");
+            let mut log = vec![];
+            Debugger::new(&pc).run("continue 10\ncontinue".as_bytes(), &mut log);
+            let log = String::from_utf8(log).unwrap();
+            let lines: Vec<&str> = log.lines().collect();
+            assert_eq!(
+                lines,
+                vec![
+                    "step limit reached (10)",
+                    "ip=0   A=1 B=0 C=0",
+                    &format!("step limit reached ({MAX_STEPS})"),
+                    "ip=0   A=1 B=0 C=0",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_trace_and_commands() {
+            let log = session("trace on\nstep\nset a 5\nfoo\nreset\ndisasm\nquit\nregs");
+            let lines: Vec<&str> = log.lines().collect();
+            assert_eq!(
+                lines,
+                vec![
+                    "trace enabled",
+                    "  0: adv 1    | ip=0   A=729 B=0 C=0 -> ip=2   A=364 B=0 C=0",
+                    "ip=2   A=364 B=0 C=0",
+                    "ip=2   A=5 B=0 C=0",
+                    "unknown command: foo",
+                    "ip=0   A=729 B=0 C=0",
+                    "=>   0: adv 1    ; A = A >> 1",
+                    "     2: out A    ; out A % 8",
+                    "     4: jnz 0    ; if A != 0: goto 0",
+                ]
+            );
+        }
+    }
+}
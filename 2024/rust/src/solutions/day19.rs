@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use aoc_utils::memo::Memo;
 
 #[derive(Debug, Clone)]
 pub struct Problem {
@@ -38,26 +38,17 @@ pub fn matches(i: &str, tokens: &[String]) -> bool {
     false
 }
 
-pub fn count_matches<'a: 'b, 'b>(
-    i: &'a str,
-    tokens: &[String],
-    cache: &'b mut HashMap<&'a str, usize>,
-) -> usize {
-    if i.is_empty() {
-        return 1;
-    }
-    if let Some(already) = cache.get(i) {
-        return *already;
-    }
-    let mut found = 0;
-    for t in tokens {
-        if i.starts_with(t) {
-            let (_, newrem) = i.split_at(t.len());
-            found += count_matches(newrem, tokens, cache);
+pub fn count_matches<'a>(i: &'a str, tokens: &[String], memo: &mut Memo<&'a str, usize>) -> usize {
+    memo.get(i, |memo| {
+        if i.is_empty() {
+            return 1;
         }
-    }
-    cache.insert(i, found);
-    found
+        tokens
+            .iter()
+            .filter_map(|t| i.strip_prefix(t.as_str()))
+            .map(|newrem| count_matches(newrem, tokens, memo))
+            .sum()
+    })
 }
 
 pub fn part1(p: &Problem) -> usize {
@@ -72,9 +63,9 @@ pub fn part1(p: &Problem) -> usize {
 
 pub fn part2(p: &Problem) -> usize {
     let mut possible = 0;
-    let cache = &mut HashMap::new();
+    let memo = &mut Memo::new();
     for towel in &p.towels {
-        possible += count_matches(towel, &p.tokens, cache);
+        possible += count_matches(towel, &p.tokens, memo);
     }
     possible
 }
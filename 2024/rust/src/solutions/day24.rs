@@ -1,4 +1,4 @@
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, HashMap};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 enum State<'a> {
@@ -79,6 +79,231 @@ impl<'a> Circuit<'a> {
         }
         diff
     }
+
+    fn swap(&mut self, w1: &'a str, w2: &'a str) {
+        let s1 = self.states.remove(w1).expect("unknown wire");
+        let s2 = self.states.remove(w2).expect("unknown wire");
+        self.states.insert(w1, s2);
+        self.states.insert(w2, s1);
+    }
+
+    fn gate(&self, wire: &str) -> Option<(Gate, &'a str, &'a str)> {
+        match *self.states.get(wire)? {
+            State::Value(_) => None,
+            State::And(a, b) => Some((Gate::And, a, b)),
+            State::Or(a, b) => Some((Gate::Or, a, b)),
+            State::Xor(a, b) => Some((Gate::Xor, a, b)),
+        }
+    }
+
+    /// Check the circuit against the layout of a ripple-carry adder, bit by bit:
+    ///
+    /// ```text
+    /// sum_i   = x_i XOR y_i        gen_i   = x_i AND y_i
+    /// z_i     = sum_i XOR c_(i-1)  prop_i  = sum_i AND c_(i-1)
+    /// c_i     = gen_i OR prop_i    z_n     = c_(n-1)
+    /// ```
+    ///
+    /// Every time a gate does not match, the pair of swapped outputs that explains it is
+    /// recorded and swapped back before moving on, so the result is the list of swaps needed to
+    /// fix the adder (empty if it has no defects).
+    pub fn verify(&self) -> Result<Vec<Defect<'a>>, String> {
+        let mut adder = Adder::new(self);
+        let xs: Vec<&str> = self.xs.iter().rev().copied().collect();
+        let ys: Vec<&str> = self.ys.iter().rev().copied().collect();
+        let zs: Vec<&str> = self.zs.iter().rev().copied().collect();
+        if xs.len() != ys.len() || !(xs.len()..=xs.len() + 1).contains(&zs.len()) {
+            return Err(format!(
+                "expected two inputs of the same width and an output with at most one extra bit, \
+                 found {}, {} and {} bits",
+                xs.len(),
+                ys.len(),
+                zs.len()
+            ));
+        }
+
+        let mut carry: Option<&str> = None;
+        for bit in 0..xs.len() {
+            let (x, y, z) = (xs[bit], ys[bit], zs[bit]);
+            let missing = |what: String| format!("bit {bit}: could not find {what}");
+            let sum = adder
+                .find(Gate::Xor, x, y)
+                .ok_or_else(|| missing(format!("{x} XOR {y}")))?;
+
+            let Some(mut c) = carry else {
+                if sum != z {
+                    adder.swap(bit, sum, z, format!("{z} should be {x} XOR {y}"));
+                }
+                carry = Some(
+                    adder
+                        .find(Gate::And, x, y)
+                        .ok_or_else(|| missing(format!("{x} AND {y}")))?,
+                );
+                continue;
+            };
+
+            match adder.find(Gate::Xor, sum, c) {
+                Some(w) if w == z => {}
+                Some(w) => adder.swap(
+                    bit,
+                    w,
+                    z,
+                    format!("{z} should be {sum} XOR {c}, but that gate drives {w}"),
+                ),
+                None => match adder.circuit.gate(z) {
+                    Some((Gate::Xor, a, b)) if a == c || b == c => {
+                        let other = if a == c { b } else { a };
+                        adder.swap(
+                            bit,
+                            sum,
+                            other,
+                            format!("{z} takes {other} instead of {x} XOR {y} ({sum})"),
+                        );
+                    }
+                    Some((Gate::Xor, a, b)) if a == sum || b == sum => {
+                        let other = if a == sum { b } else { a };
+                        adder.swap(
+                            bit,
+                            c,
+                            other,
+                            format!("{z} takes {other} instead of the carry {c}"),
+                        );
+                        c = other;
+                    }
+                    gate => {
+                        return Err(format!(
+                            "bit {bit}: {z} should be {sum} XOR {c}, found {gate:?}"
+                        ))
+                    }
+                },
+            }
+
+            let sum = adder.find(Gate::Xor, x, y).expect("sum gate disappeared");
+            let generate = adder
+                .find(Gate::And, x, y)
+                .ok_or_else(|| missing(format!("{x} AND {y}")))?;
+            let propagate = adder
+                .find(Gate::And, sum, c)
+                .ok_or_else(|| missing(format!("{sum} AND {c}")))?;
+            let next = match adder.find(Gate::Or, generate, propagate) {
+                Some(w) => w,
+                None => {
+                    let (found, other) = adder
+                        .find_with(Gate::Or, generate)
+                        .map(|o| (generate, o))
+                        .or_else(|| adder.find_with(Gate::Or, propagate).map(|o| (propagate, o)))
+                        .ok_or_else(|| missing(format!("{generate} OR {propagate}")))?;
+                    let wrong = if found == generate {
+                        propagate
+                    } else {
+                        generate
+                    };
+                    adder.swap(
+                        bit,
+                        wrong,
+                        other,
+                        format!("the carry takes {other} instead of {wrong}"),
+                    );
+                    adder
+                        .find(Gate::Or, found, other)
+                        .expect("carry gate disappeared")
+                }
+            };
+            carry = Some(next);
+        }
+
+        if let (Some(c), Some(&z)) = (carry, zs.get(xs.len())) {
+            if c != z {
+                adder.swap(
+                    xs.len(),
+                    c,
+                    z,
+                    format!("{z} should be the last carry, which drives {c}"),
+                );
+            }
+        }
+        Ok(adder.defects)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Gate {
+    And,
+    Or,
+    Xor,
+}
+
+/// A wrong gate in an adder, and the swap of outputs that fixes it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Defect<'a> {
+    /// Bit of the adder where the defect was found
+    pub bit: usize,
+    /// Outputs that have to be swapped back
+    pub wires: (&'a str, &'a str),
+    /// Which gate is wrong, and why
+    pub reason: String,
+}
+
+impl std::fmt::Display for Defect<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (w1, w2) = self.wires;
+        write!(f, "bit {}: swap {w1} and {w2} ({})", self.bit, self.reason)
+    }
+}
+
+/// A circuit with an index of gates by type and inputs, used to verify adders
+struct Adder<'a> {
+    circuit: Circuit<'a>,
+    gates: HashMap<(Gate, &'a str, &'a str), &'a str>,
+    defects: Vec<Defect<'a>>,
+}
+
+impl<'a> Adder<'a> {
+    fn new(circuit: &Circuit<'a>) -> Self {
+        let mut adder = Adder {
+            circuit: circuit.clone(),
+            gates: Default::default(),
+            defects: vec![],
+        };
+        adder.index();
+        adder
+    }
+
+    fn index(&mut self) {
+        self.gates = self
+            .circuit
+            .states
+            .keys()
+            .filter_map(|&out| {
+                let (gate, a, b) = self.circuit.gate(out)?;
+                Some(((gate, a.min(b), a.max(b)), out))
+            })
+            .collect();
+    }
+
+    fn find(&self, gate: Gate, a: &str, b: &str) -> Option<&'a str> {
+        self.gates.get(&(gate, a.min(b), a.max(b))).copied()
+    }
+
+    /// Find a gate that takes `input`, returning its other input
+    fn find_with(&self, gate: Gate, input: &str) -> Option<&'a str> {
+        self.gates.keys().find_map(|&(g, a, b)| match g {
+            _ if g != gate => None,
+            _ if a == input => Some(b),
+            _ if b == input => Some(a),
+            _ => None,
+        })
+    }
+
+    fn swap(&mut self, bit: usize, w1: &'a str, w2: &'a str, reason: String) {
+        self.circuit.swap(w1, w2);
+        self.index();
+        self.defects.push(Defect {
+            bit,
+            wires: (w1, w2),
+            reason,
+        });
+    }
 }
 
 pub fn parse(i: &str) -> Circuit<'_> {
@@ -154,68 +379,116 @@ fn count_defects<'a>(c: &'a Circuit) -> BTreeMap<Vec<&'a str>, Vec<(usize, usize
 }
 
 pub fn part2<'a: 'b, 'b>(input: &'b Circuit<'a>) -> String {
-    let mut c = input.clone();
-
-    let swaps = [
-        ["z07", "rts"],
-        ["z12", "jpj"],
-        ["z26", "kgj"],
-        ["chv", "vvw"],
-    ];
-    for [t1, t2] in swaps {
-        let s1 = c.states.remove(t1).unwrap();
-        let s2 = c.states.remove(t2).unwrap();
-        c.states.insert(t1, s2);
-        c.states.insert(t2, s1);
-    }
-
-    // I've used this code to generate a graph of dependencies for every
-    // output and a list of faulty outputs for every input pin.
-    //
-    // The list of faulty outputs can be used to detect which two pins are affected
-    // by a given output. Then, the graph is used to select two candidate pins to
-    // swap.
-    //
-    // This whole process could be automated, but some pin swaps generate loops, which end
-    // up crashing the program.
-    let debugging = false;
-    if debugging {
-        let mut graph: BTreeMap<&str, Vec<&str>> = Default::default();
-        for (output, state) in &c.states {
-            match state {
-                State::Value(_) => {}
-                State::And(a, b) | State::Or(a, b) | State::Xor(a, b) => {
-                    graph.entry(output).or_default().extend([a, b]);
-                }
+    let defects = input
+        .verify()
+        .unwrap_or_else(|err| panic!("not a ripple-carry adder ({err})"));
+    for defect in &defects {
+        aoc_utils::dbg!(defect.to_string());
+    }
+
+    let mut fixed = input.clone();
+    for defect in &defects {
+        fixed.swap(defect.wires.0, defect.wires.1);
+    }
+    let mut wires: Vec<_> = defects
+        .iter()
+        .flat_map(|d| [d.wires.0, d.wires.1])
+        .collect();
+    wires.sort();
+    let wires = wires.join(",");
+
+    let remaining = count_defects(&fixed);
+    let outputs: Vec<_> = remaining.keys().collect();
+    assert!(
+        outputs.is_empty(),
+        "swapping {wires} does not fix the adder, wrong outputs: {outputs:?}"
+    );
+    wires
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A ripple-carry adder of `bits` bits that computes `x + y`, with some pairs of gate
+    /// outputs swapped
+    fn adder(bits: usize, x: usize, y: usize, swaps: &[(&str, &str)]) -> String {
+        let mut lines = vec![];
+        for (name, value) in [("x", x), ("y", y)] {
+            for bit in 0..bits {
+                lines.push(format!("{name}{bit:02}: {}", (value >> bit) & 1));
             }
         }
-        let mut outgraph: BTreeMap<&str, Vec<&str>> = Default::default();
-        let mut seen: HashSet<&str> = Default::default();
-        for o in input.zs.iter().rev() {
-            eprintln!("*** {o} {:?}", c.states.get(o).unwrap());
-            let mut pending = vec![*o];
-            while let Some(nxt) = pending.pop() {
-                for n in graph.get(nxt).cloned().unwrap_or_default() {
-                    eprintln!("{nxt} -> {n} {:?}", c.states.get(n).unwrap());
-                    outgraph.entry(o).or_default().push(n);
-                    if seen.contains(n) {
-                        continue;
-                    }
-                    seen.insert(n);
-                    pending.push(n);
-                }
+        lines.push(String::new());
+        let mut gates = vec![];
+        for bit in 0..bits {
+            let carry = if bit == bits - 1 {
+                format!("z{bits:02}")
+            } else {
+                format!("c{bit:02}")
+            };
+            if bit == 0 {
+                gates.push("x00 XOR y00 -> z00".to_string());
+                gates.push(format!("x00 AND y00 -> {carry}"));
+                continue;
             }
+            let prev = format!("c{:02}", bit - 1);
+            gates.push(format!("x{bit:02} XOR y{bit:02} -> s{bit:02}"));
+            gates.push(format!("s{bit:02} XOR {prev} -> z{bit:02}"));
+            gates.push(format!("x{bit:02} AND y{bit:02} -> g{bit:02}"));
+            gates.push(format!("s{bit:02} AND {prev} -> p{bit:02}"));
+            gates.push(format!("g{bit:02} OR p{bit:02} -> {carry}"));
         }
+        for gate in gates {
+            let (inputs, output) = gate.split_once(" -> ").unwrap();
+            let output = swaps
+                .iter()
+                .find_map(|&(a, b)| match output {
+                    _ if output == a => Some(b),
+                    _ if output == b => Some(a),
+                    _ => None,
+                })
+                .unwrap_or(output);
+            lines.push(format!("{inputs} -> {output}"));
+        }
+        lines.join("\n")
+    }
 
-        let outs = count_defects(&c);
+    #[test]
+    fn test_clean_adder() {
+        let text = adder(4, 11, 6, &[]);
+        let circuit = parse(&text);
+        assert_eq!(circuit.verify(), Ok(vec![]));
+        assert_eq!(part1(&circuit), 17);
+        assert_eq!(part2(&circuit), "");
+    }
 
-        for (out, points) in outs {
-            for (x, y) in points {
-                eprintln!("({x}, {y}) -> {out:?}");
-            }
-        }
+    #[test]
+    fn test_one_swap() {
+        let text = adder(4, 11, 6, &[("z02", "c02")]);
+        let circuit = parse(&text);
+        let defects = circuit.verify().unwrap();
+        assert_eq!(defects.len(), 1);
+        assert_eq!(defects[0].bit, 2);
+        assert_eq!(defects[0].wires, ("c02", "z02"));
+        assert_eq!(part2(&circuit), "c02,z02");
+    }
+
+    #[test]
+    fn test_two_swaps() {
+        let text = adder(4, 5, 3, &[("s01", "g01"), ("z02", "c02")]);
+        let circuit = parse(&text);
+        let defects = circuit.verify().unwrap();
+        let bits: Vec<usize> = defects.iter().map(|d| d.bit).collect();
+        assert_eq!(bits, vec![1, 2]);
+        assert_eq!(part2(&circuit), "c02,g01,s01,z02");
+    }
+
+    #[test]
+    #[should_panic(expected = "not a ripple-carry adder")]
+    fn test_not_an_adder() {
+        let circuit = parse("x00: 1\ny00: 1\n\nx00 OR y00 -> z00");
+        assert!(circuit.verify().is_err());
+        part2(&circuit);
     }
-    let mut wires: Vec<_> = swaps.iter().flatten().cloned().collect();
-    wires.sort();
-    wires.join(",")
 }
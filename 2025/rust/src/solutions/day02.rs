@@ -1,22 +1,21 @@
-use std::ops::RangeInclusive;
+use aoc_utils::intervals::IntervalSet;
 
-type ValidRange = RangeInclusive<usize>;
+type ValidRanges = IntervalSet<usize>;
 
-pub fn parse(input: &str) -> Vec<ValidRange> {
-    input
-        .trim()
-        .split(',')
-        .map(|range| {
-            let (start, end) = range.split_once('-').expect("There should only be ranges");
-            start.parse().expect("invalid number")..=end.parse().expect("invalid number")
-        })
-        .collect()
+pub fn parse(input: &str) -> ValidRanges {
+    let mut ranges = IntervalSet::new();
+    for range in input.trim().split(',') {
+        let (start, end) = range.split_once('-').expect("There should only be ranges");
+        ranges.insert_inclusive(
+            start.parse().expect("invalid number")..=end.parse().expect("invalid number"),
+        );
+    }
+    ranges
 }
 
-pub fn part1(ranges: &[ValidRange]) -> usize {
+pub fn part1(ranges: &ValidRanges) -> usize {
     ranges
         .iter()
-        .cloned()
         .flat_map(|range| {
             range.filter(|num| {
                 let st = num.to_string();
@@ -29,10 +28,9 @@ pub fn part1(ranges: &[ValidRange]) -> usize {
         .sum()
 }
 
-pub fn part2(ranges: &[ValidRange]) -> usize {
+pub fn part2(ranges: &ValidRanges) -> usize {
     ranges
         .iter()
-        .cloned()
         .flat_map(|range| range.filter(|&num| filter_str(num)))
         .sum()
 }
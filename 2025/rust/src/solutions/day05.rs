@@ -1,3 +1,4 @@
+use aoc_utils::intervals::IntervalSet;
 use nom::{
     IResult, Parser,
     character::complete::{char, digit1, newline},
@@ -9,7 +10,7 @@ use std::ops::RangeInclusive;
 
 #[derive(Debug, Clone)]
 pub struct Input {
-    ranges: Vec<RangeInclusive<usize>>,
+    fresh: IntervalSet<usize>,
     ids: Vec<usize>,
 }
 
@@ -31,7 +32,11 @@ fn parse_input(input: &str) -> IResult<&str, Input> {
     )
     .parse(input)?;
 
-    Ok((input, Input { ranges, ids }))
+    let mut fresh = IntervalSet::new();
+    for range in ranges {
+        fresh.insert_inclusive(range);
+    }
+    Ok((input, Input { fresh, ids }))
 }
 
 pub fn parse(input: &str) -> Input {
@@ -39,42 +44,9 @@ pub fn parse(input: &str) -> Input {
 }
 
 pub fn part1(i: &Input) -> usize {
-    let mut i = i.clone();
-    i.ranges.sort_by_key(|r| *r.start());
-    i.ids
-        .iter()
-        .filter(|id| {
-            let mut idx = i.ranges.partition_point(|range| range.start() < id);
-            while idx > 0 {
-                idx -= 1;
-                let rng = &i.ranges[idx];
-                if rng.start() <= id && rng.end() >= id {
-                    return true;
-                }
-            }
-            false
-        })
-        .count()
+    i.ids.iter().filter(|&&id| i.fresh.contains(id)).count()
 }
 
 pub fn part2(i: &Input) -> usize {
-    let mut i = i.clone();
-    i.ranges.sort_by_key(|r| *r.start());
-
-    let mut idx = 1;
-
-    while idx < i.ranges.len() {
-        let r1 = &i.ranges[idx - 1];
-        let r2 = &i.ranges[idx];
-
-        if r1.contains(r2.end()) {
-            i.ranges.remove(idx);
-            continue;
-        } else if r1.end() >= r2.start() {
-            i.ranges[idx - 1] = *r1.start()..=r2.start() - 1;
-        }
-        idx += 1;
-    }
-
-    i.ranges.into_iter().map(|range| range.count()).sum()
+    i.fresh.len()
 }
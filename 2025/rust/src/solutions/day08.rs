@@ -1,5 +1,4 @@
-use std::cmp::Reverse;
-use std::collections::{BinaryHeap, HashMap};
+use aoc_utils::unionfind::UnionFind;
 
 type Coord = (isize, isize, isize);
 
@@ -19,61 +18,44 @@ pub fn parse(i: &str) -> Vec<Coord> {
         .collect()
 }
 
-pub fn part1(coords: &[Coord]) -> usize {
-    solve(coords, false)
-}
-
-pub fn solve(coords: &[Coord], tillend: bool) -> usize {
-    let mut circuits: HashMap<usize, Vec<usize>> = coords
-        .iter()
-        .enumerate()
-        .map(|(ix, _coord)| (ix, vec![ix]))
-        .collect();
-    let mut belonging: Vec<usize> = (0..coords.len()).collect();
-    let mut distances: BinaryHeap<Reverse<(isize, (usize, usize))>> = coords
+/// Every pair of boxes, closest first
+fn closest_pairs(coords: &[Coord]) -> Vec<(usize, usize)> {
+    let mut pairs: Vec<(isize, usize, usize)> = coords
         .iter()
         .enumerate()
         .flat_map(|(ix1, c1)| {
-            coords.iter().enumerate().filter(move |(ix2, _)| ix1 < *ix2).map(move |(ix2, c2)| {
-                    (
-                        (c1.0 - c2.0).pow(2) + (c1.1 - c2.1).pow(2) + (c1.2 - c2.2).pow(2),
-                        (ix1, ix2),
-                    )
+            coords[ix1 + 1..].iter().enumerate().map(move |(offset, c2)| {
+                (
+                    (c1.0 - c2.0).pow(2) + (c1.1 - c2.1).pow(2) + (c1.2 - c2.2).pow(2),
+                    ix1,
+                    ix1 + 1 + offset,
+                )
             })
         })
-        .map(Reverse)
         .collect();
+    pairs.sort_unstable();
+    pairs.into_iter().map(|(_, ix1, ix2)| (ix1, ix2)).collect()
+}
 
-    for connected in 0.. {
-        let Some(Reverse((_dist, (ix1, ix2)))) = distances.pop() else {
-            panic!("not enough pairs");
-        };
-        let circuit1 = belonging[ix1];
-        let circuit2 = belonging[ix2];
-        if circuit1 == circuit2 {
-            continue;
-        }
-        let toremove = circuit1.min(circuit2);
-        let removed = circuits.remove(&toremove).expect("circuit without boxes!");
-        let target = circuit1.max(circuit2);
-        let group = circuits.get_mut(&target).expect("target without boxes");
-        for pos in removed {
-            belonging[pos] = target;
-            group.push(pos);
-        }
-        if tillend {
-            if circuits.len() == 1 {
-                return (coords[ix1].0 * coords[ix2].0) as usize;
-            }
-        } else if connected > 1_000 {
-            break;
-        }
+/// Product of the sizes of the three largest circuits, after connecting the closest `n` pairs
+pub fn largest_circuits(coords: &[Coord], n: usize) -> usize {
+    let mut circuits = UnionFind::new(coords.len());
+    for (ix1, ix2) in closest_pairs(coords).into_iter().take(n) {
+        circuits.union(ix1, ix2);
     }
-    let mut sizes: Vec<_> = circuits.values().map(|g| g.len()).collect();
-    sizes.sort_unstable();
-    sizes.iter().rev().take(3).product()
+    circuits.sizes().iter().take(3).product()
+}
+
+pub fn part1(coords: &[Coord]) -> usize {
+    largest_circuits(coords, 1000)
 }
 
 pub fn part2(coords: &[Coord]) -> usize {
-    solve(coords, true)
+    let mut circuits = UnionFind::new(coords.len());
+    for (ix1, ix2) in closest_pairs(coords) {
+        if circuits.union(ix1, ix2) && circuits.components() == 1 {
+            return (coords[ix1].0 * coords[ix2].0) as usize;
+        }
+    }
+    panic!("not enough pairs");
 }
@@ -1,3 +1,4 @@
+use std::env;
 use std::fs;
 use std::fs::File;
 use std::io::Write;
@@ -8,9 +9,18 @@ pub fn main() {
 
     let dest_path = Path::new("src").join("main.rs");
     let mut f = File::create(&dest_path).unwrap();
-    f.write_all(b"mod solutions;\n\n").unwrap();
-    f.write_all(b"fn main() {\n    solutions::main();\n}\n")
-        .unwrap();
+    let lib_path = Path::new("src").join("lib.rs");
+    if lib_path.exists() {
+        // The solutions are compiled once in the library, and shared with any extra binaries
+        println!("cargo:rerun-if-changed={}", lib_path.to_str().unwrap());
+        let krate = env::var("CARGO_PKG_NAME").unwrap().replace('-', "_");
+        f.write_all(format!("fn main() {{\n    {krate}::solutions::main();\n}}\n").as_bytes())
+            .unwrap();
+    } else {
+        f.write_all(b"mod solutions;\n\n").unwrap();
+        f.write_all(b"fn main() {\n    solutions::main();\n}\n")
+            .unwrap();
+    }
 
     let dest_path = Path::new("src").join("solutions.rs");
     let mut f = File::create(&dest_path).unwrap();
@@ -0,0 +1,580 @@
+//! Cellular automata: every cell changes at the same time, depending on its own state and the
+//! state of its neighbors.
+//!
+//! Neighborhoods are lists of offsets, and rules get the state of the cell and the states of
+//! its neighbors, in the same order as the offsets. There are two backends:
+//!
+//! * `Dense` stores every cell in an N-dimensional box, with any type of state. Cells outside
+//!   the box have a background state, which can be fixed, evolve like any other cell (for
+//!   infinite grids), or the grid can wrap around. `Bits` is a `Dense` grid of booleans that
+//!   only uses a bit per cell.
+//! * `Sparse` only stores the cells that differ from the background, and its states are
+//!   booleans. It suits patterns that grow in every direction, like the game of life.
+//!
+//! `fixpoint` and `run_cycles` work with any state that can be advanced one step at a time.
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+pub type Point<const N: usize> = [isize; N];
+
+fn add<const N: usize>(a: Point<N>, b: Point<N>) -> Point<N> {
+    let mut sum = a;
+    for (s, d) in sum.iter_mut().zip(b.iter()) {
+        *s += d;
+    }
+    sum
+}
+
+/// Offsets to every cell around the center (8 in 2D, 26 in 3D...), the last axis changing
+/// fastest
+pub fn moore<const N: usize>() -> Vec<Point<N>> {
+    (0..3usize.pow(N as u32))
+        .map(|mut ix| {
+            let mut offset = [0; N];
+            for d in offset.iter_mut().rev() {
+                *d = (ix % 3) as isize - 1;
+                ix /= 3;
+            }
+            offset
+        })
+        .filter(|offset| offset.iter().any(|&d| d != 0))
+        .collect()
+}
+
+/// Offsets to the cells that share a side with the center (4 in 2D, 6 in 3D...)
+pub fn von_neumann<const N: usize>() -> Vec<Point<N>> {
+    (0..N)
+        .flat_map(|axis| {
+            [-1, 1].iter().map(move |&d| {
+                let mut offset = [0; N];
+                offset[axis] = d;
+                offset
+            })
+        })
+        .collect()
+}
+
+/// Neighbors on a grid of hexagons, in axial coordinates `[q, r]` (same order as
+/// `hex::Direction::ALL`)
+pub const HEXAGONAL: [Point<2>; 6] = [[1, 0], [0, 1], [-1, 1], [-1, 0], [0, -1], [1, -1]];
+
+/// Rule of the game of life and its variants: a dead cell becomes alive if the number of live
+/// neighbors is in `born`, and a live cell stays alive if it is in `survive`.
+pub fn life_like(
+    born: &'static [usize],
+    survive: &'static [usize],
+) -> impl Fn(bool, &[bool]) -> bool + Clone {
+    move |alive, neighbors| {
+        let count = neighbors.iter().filter(|&&n| n).count();
+        if alive {
+            survive.contains(&count)
+        } else {
+            born.contains(&count)
+        }
+    }
+}
+
+/// Call `step` until it reports that nothing changed. Returns the number of calls, including
+/// the last one.
+pub fn fixpoint(mut step: impl FnMut() -> bool) -> usize {
+    let mut steps = 1;
+    while step() {
+        steps += 1;
+    }
+    steps
+}
+
+/// Advance `state` by `generations` steps. Once a state repeats, whole cycles are skipped.
+pub fn run_cycles<T: Clone + Hash + Eq>(
+    state: &mut T,
+    generations: usize,
+    mut step: impl FnMut(&mut T),
+) {
+    let mut seen: HashMap<T, usize> = HashMap::new();
+    let mut done = 0;
+    while done < generations {
+        if let Some(previous) = seen.insert(state.clone(), done) {
+            let period = done - previous;
+            for _ in 0..(generations - done) % period {
+                step(state);
+            }
+            return;
+        }
+        step(state);
+        done += 1;
+    }
+}
+
+/// What is outside the box of a `Dense` grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Edges {
+    /// The background never changes
+    Fixed,
+    /// The grid is infinite. The background changes with the rule (as a cell surrounded by
+    /// background cells), and the box grows on every step to include the cells that could be
+    /// affected by the cells inside.
+    Infinite,
+    /// Each side of the box is next to the opposite side
+    Wrap,
+}
+
+/// Storage for the states of the cells of a `Dense` grid, by index
+pub trait Cells<S: Copy + PartialEq>: Clone + PartialEq + FromIterator<S> {
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn get(&self, ix: usize) -> S;
+
+    fn set(&mut self, ix: usize, state: S);
+
+    /// Number of cells with the given state
+    fn count(&self, state: S) -> usize {
+        (0..self.len()).filter(|&ix| self.get(ix) == state).count()
+    }
+}
+
+impl<S: Copy + PartialEq> Cells<S> for Vec<S> {
+    fn len(&self) -> usize {
+        <[S]>::len(self)
+    }
+
+    fn get(&self, ix: usize) -> S {
+        self[ix]
+    }
+
+    fn set(&mut self, ix: usize, state: S) {
+        self[ix] = state;
+    }
+}
+
+/// Booleans packed in 64-bit words
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BitSet {
+    /// The unused bits of the last word are always zero
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl FromIterator<bool> for BitSet {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        let mut bits = BitSet::default();
+        for state in iter {
+            if bits.len % 64 == 0 {
+                bits.words.push(0);
+            }
+            bits.len += 1;
+            bits.set(bits.len - 1, state);
+        }
+        bits
+    }
+}
+
+impl Cells<bool> for BitSet {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn get(&self, ix: usize) -> bool {
+        assert!(ix < self.len, "index out of bounds");
+        self.words[ix / 64] & (1 << (ix % 64)) != 0
+    }
+
+    fn set(&mut self, ix: usize, state: bool) {
+        assert!(ix < self.len, "index out of bounds");
+        let mask = 1 << (ix % 64);
+        if state {
+            self.words[ix / 64] |= mask;
+        } else {
+            self.words[ix / 64] &= !mask;
+        }
+    }
+
+    fn count(&self, state: bool) -> usize {
+        let ones: usize = self.words.iter().map(|w| w.count_ones() as usize).sum();
+        if state {
+            ones
+        } else {
+            self.len - ones
+        }
+    }
+}
+
+/// The states of the cells are stored in `C`, a `Vec<S>` unless stated otherwise
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Dense<S, const N: usize, C = Vec<S>> {
+    /// Coordinates of the first cell of the box
+    origin: Point<N>,
+    dims: [usize; N],
+    /// States of the cells in the box, with the last axis changing fastest
+    cells: C,
+    background: S,
+    edges: Edges,
+}
+
+/// `Dense` grid of booleans with a bit per cell
+pub type Bits<const N: usize> = Dense<bool, N, BitSet>;
+
+impl<S: Copy + PartialEq> Dense<S, 2> {
+    pub fn rows(&self) -> impl Iterator<Item = &[S]> {
+        self.cells.chunks(self.dims[1].max(1))
+    }
+}
+
+impl Bits<2> {
+    pub fn rows(&self) -> impl Iterator<Item = impl Iterator<Item = bool> + '_> {
+        let width = self.dims[1];
+        (0..self.dims[0])
+            .map(move |row| (row * width..(row + 1) * width).map(|ix| self.cells.get(ix)))
+    }
+}
+
+impl<S: Copy + PartialEq, C: Cells<S>> Dense<S, 2, C> {
+    /// Grid with the given rows, and its origin at `[0, 0]`. Points are `[row, column]`.
+    pub fn from_rows(rows: Vec<Vec<S>>, background: S, edges: Edges) -> Self {
+        let width = rows.first().map_or(0, |row| row.len());
+        assert!(
+            rows.iter().all(|row| row.len() == width),
+            "rows of different lengths"
+        );
+        Dense {
+            origin: [0, 0],
+            dims: [rows.len(), width],
+            cells: rows.into_iter().flatten().collect(),
+            background,
+            edges,
+        }
+    }
+}
+
+impl<S: Copy + PartialEq, const N: usize, C: Cells<S>> Dense<S, N, C> {
+    /// Box with `dims` cells on each axis starting at `origin`, all with the background state
+    pub fn new(origin: Point<N>, dims: [usize; N], background: S, edges: Edges) -> Self {
+        Dense {
+            origin,
+            dims,
+            cells: std::iter::repeat_n(background, dims.iter().product()).collect(),
+            background,
+            edges,
+        }
+    }
+
+    pub fn origin(&self) -> Point<N> {
+        self.origin
+    }
+
+    pub fn dims(&self) -> [usize; N] {
+        self.dims
+    }
+
+    pub fn background(&self) -> S {
+        self.background
+    }
+
+    fn index(&self, p: Point<N>) -> Option<usize> {
+        let mut ix = 0;
+        for ((&c, &o), &len) in p.iter().zip(&self.origin).zip(&self.dims) {
+            let mut d = c - o;
+            if self.edges == Edges::Wrap {
+                d = d.rem_euclid(len as isize);
+            } else if d < 0 || d >= len as isize {
+                return None;
+            }
+            ix = ix * len + d as usize;
+        }
+        Some(ix)
+    }
+
+    fn point(&self, mut ix: usize) -> Point<N> {
+        let mut p = self.origin;
+        for axis in (0..N).rev() {
+            p[axis] += (ix % self.dims[axis]) as isize;
+            ix /= self.dims[axis];
+        }
+        p
+    }
+
+    pub fn get(&self, p: Point<N>) -> S {
+        self.index(p)
+            .map_or(self.background, |ix| self.cells.get(ix))
+    }
+
+    /// Change a cell. Panics if the cell is outside the box.
+    pub fn set(&mut self, p: Point<N>, state: S) {
+        let ix = self.index(p).expect("cell outside the grid");
+        self.cells.set(ix, state);
+    }
+
+    /// Every cell in the box, and its state
+    pub fn cells(&self) -> impl Iterator<Item = (Point<N>, S)> + '_ {
+        (0..self.cells.len()).map(move |ix| (self.point(ix), self.cells.get(ix)))
+    }
+
+    /// Number of cells in the box with the given state
+    pub fn count(&self, state: S) -> usize {
+        self.cells.count(state)
+    }
+
+    /// Compute the next state of every cell in the box. Returns false if nothing changed.
+    pub fn step(&mut self, neighborhood: &[Point<N>], rule: impl Fn(S, &[S]) -> S) -> bool {
+        let mut next = self.clone();
+        if self.edges == Edges::Infinite {
+            let radius = neighborhood
+                .iter()
+                .flat_map(|offset| offset.iter())
+                .map(|d| d.unsigned_abs())
+                .max()
+                .unwrap_or(0);
+            next = Dense::new(
+                self.origin.map(|o| o - radius as isize),
+                self.dims.map(|d| d + 2 * radius),
+                rule(self.background, &vec![self.background; neighborhood.len()]),
+                Edges::Infinite,
+            );
+        }
+        let mut buffer = Vec::with_capacity(neighborhood.len());
+        let mut changed = next.background != self.background;
+        for ix in 0..next.cells.len() {
+            let p = next.point(ix);
+            buffer.clear();
+            buffer.extend(neighborhood.iter().map(|&offset| self.get(add(p, offset))));
+            let state = self.get(p);
+            let new = rule(state, &buffer);
+            next.cells.set(ix, new);
+            changed |= new != state;
+        }
+        *self = next;
+        changed
+    }
+
+    /// Like `step`, for rules that need more than a fixed neighborhood (e.g. the first cell
+    /// in each direction that matches a condition). `rule` gets the current grid and each
+    /// point in the box. The box and the background do not change.
+    pub fn step_with(&mut self, rule: impl Fn(&Self, Point<N>) -> S) -> bool {
+        let cells: C = (0..self.cells.len())
+            .map(|ix| rule(self, self.point(ix)))
+            .collect();
+        let changed = cells != self.cells;
+        self.cells = cells;
+        changed
+    }
+}
+
+/// Cells with a boolean state, which only stores the cells that differ from the background
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sparse<const N: usize> {
+    cells: HashSet<Point<N>>,
+    background: bool,
+}
+
+impl<const N: usize> Sparse<N> {
+    /// Every cell is dead (`false`)
+    pub fn new() -> Self {
+        Sparse {
+            cells: HashSet::new(),
+            background: false,
+        }
+    }
+
+    pub fn background(&self) -> bool {
+        self.background
+    }
+
+    pub fn get(&self, p: Point<N>) -> bool {
+        self.cells.contains(&p) != self.background
+    }
+
+    pub fn set(&mut self, p: Point<N>, state: bool) {
+        if state == self.background {
+            self.cells.remove(&p);
+        } else {
+            self.cells.insert(p);
+        }
+    }
+
+    /// Number of cells that differ from the background (e.g. live cells)
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Cells that differ from the background
+    pub fn cells(&self) -> impl Iterator<Item = &Point<N>> {
+        self.cells.iter()
+    }
+
+    /// Smallest and largest coordinates on each axis of the cells that differ from the
+    /// background
+    pub fn bounds(&self) -> Option<(Point<N>, Point<N>)> {
+        let mut cells = self.cells.iter();
+        let first = *cells.next()?;
+        Some(cells.fold((first, first), |(mut min, mut max), p| {
+            for axis in 0..N {
+                min[axis] = min[axis].min(p[axis]);
+                max[axis] = max[axis].max(p[axis]);
+            }
+            (min, max)
+        }))
+    }
+
+    /// Compute the next state of every cell. Returns false if nothing changed.
+    pub fn step(
+        &mut self,
+        neighborhood: &[Point<N>],
+        rule: impl Fn(bool, &[bool]) -> bool,
+    ) -> bool {
+        let background = rule(self.background, &vec![self.background; neighborhood.len()]);
+        // Only the cells next to a cell that differs from the background can differ from it
+        let mut candidates: HashSet<Point<N>> = self.cells.clone();
+        for &cell in &self.cells {
+            for offset in neighborhood {
+                candidates.insert(add(cell, offset.map(|d| -d)));
+            }
+        }
+        let mut buffer = Vec::with_capacity(neighborhood.len());
+        let mut cells = HashSet::new();
+        for p in candidates {
+            buffer.clear();
+            buffer.extend(neighborhood.iter().map(|&offset| self.get(add(p, offset))));
+            if rule(self.get(p), &buffer) != background {
+                cells.insert(p);
+            }
+        }
+        let changed = background != self.background || cells != self.cells;
+        self.cells = cells;
+        self.background = background;
+        changed
+    }
+}
+
+impl<const N: usize> FromIterator<Point<N>> for Sparse<N> {
+    /// Only the given cells are alive
+    fn from_iter<I: IntoIterator<Item = Point<N>>>(iter: I) -> Self {
+        Sparse {
+            cells: iter.into_iter().collect(),
+            background: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_neighborhoods() {
+        assert_eq!(moore::<2>().len(), 8);
+        assert_eq!(moore::<4>().len(), 80);
+        assert_eq!(moore::<2>()[..3], [[-1, -1], [-1, 0], [-1, 1]]);
+        assert_eq!(von_neumann::<3>().len(), 6);
+        assert_eq!(HEXAGONAL.iter().fold([0, 0], |a, &b| add(a, b)), [0, 0]);
+    }
+
+    #[test]
+    fn test_life() {
+        let life = life_like(&[3], &[2, 3]);
+        let glider = [[0, 1], [1, 2], [2, 0], [2, 1], [2, 2]];
+
+        let mut sparse: Sparse<2> = glider.iter().copied().collect();
+        for _ in 0..4 {
+            assert!(sparse.step(&moore(), &life));
+        }
+        let moved: Sparse<2> = glider.iter().map(|&p| add(p, [1, 1])).collect();
+        assert_eq!(sparse, moved);
+        assert_eq!(sparse.bounds(), Some(([1, 1], [3, 3])));
+
+        // On a 5x5 torus, the glider comes back after 20 steps
+        let mut dense: Bits<2> = Dense::new([0, 0], [5, 5], false, Edges::Wrap);
+        for &p in &glider {
+            dense.set(p, true);
+        }
+        let start = dense.clone();
+        for _ in 0..4 {
+            dense.step(&moore(), &life);
+        }
+        assert!(dense.get([2, 3]) && dense.get([-2, -2]));
+        let mut steps = 4;
+        run_cycles(&mut dense, 1_000_000, |g| {
+            g.step(&moore(), &life);
+            steps += 1;
+        });
+        assert_eq!(steps, 4 + 20);
+        assert_eq!(dense.count(true), 5);
+        assert_ne!(dense, start);
+
+        // A block never changes
+        let mut block: Dense<bool, 2> = Dense::from_rows(
+            vec![
+                vec![false; 4],
+                vec![false, true, true, false],
+                vec![false, true, true, false],
+                vec![false; 4],
+            ],
+            false,
+            Edges::Fixed,
+        );
+        assert_eq!(fixpoint(|| block.step(&moore(), &life)), 1);
+        assert_eq!(block.rows().count(), 4);
+    }
+
+    #[test]
+    fn test_infinite() {
+        // Every cell flips, so the background flips too
+        let flip = |s: bool, _: &[bool]| !s;
+        let mut sparse: Sparse<2> = [[0, 0]].iter().copied().collect();
+        assert!(sparse.step(&von_neumann(), flip));
+        assert!(sparse.background() && !sparse.get([0, 0]) && sparse.get([5, 5]));
+        assert_eq!(sparse.len(), 1);
+
+        let mut dense: Dense<bool, 2> = Dense::new([0, 0], [1, 1], false, Edges::Infinite);
+        dense.set([0, 0], true);
+        dense.step(&von_neumann(), flip);
+        assert_eq!(dense.origin(), [-1, -1]);
+        assert_eq!(dense.dims(), [3, 3]);
+        assert!(dense.background() && !dense.get([0, 0]) && dense.get([7, 7]));
+        assert_eq!(dense.count(true), 8);
+
+        // Cells become alive next to a live cell, so the pattern keeps growing
+        let grow = |s: bool, n: &[bool]| s || n.contains(&true);
+        let mut dense: Dense<bool, 2> = Dense::new([0, 0], [1, 1], false, Edges::Infinite);
+        let mut bits: Bits<2> = Dense::new([0, 0], [1, 1], false, Edges::Infinite);
+        dense.set([0, 0], true);
+        bits.set([0, 0], true);
+        for _ in 0..3 {
+            dense.step(&von_neumann(), grow);
+            bits.step(&von_neumann(), grow);
+        }
+        assert!(bits.cells().eq(dense.cells()));
+        assert_eq!(bits.count(true), 25);
+        assert_eq!(dense.dims(), [7, 7]);
+        assert!(!dense.background());
+        assert_eq!(dense.count(true), 25);
+        assert!(dense
+            .cells()
+            .all(|(p, s)| s == (p[0].abs() + p[1].abs() <= 3)));
+    }
+
+    #[test]
+    fn test_bitset() {
+        let mut bits: BitSet = (0..130).map(|ix| ix % 3 == 0).collect();
+        assert_eq!(bits.len(), 130);
+        assert_eq!(bits.count(true), 44);
+        assert_eq!(bits.count(false), 86);
+        assert!(bits.get(129) && !bits.get(128));
+        bits.set(128, true);
+        bits.set(129, false);
+        assert!(bits.get(128) && !bits.get(129));
+        assert_eq!(bits.count(true), 44);
+
+        let rows = vec![vec![true, false, false], vec![false, true, true]];
+        let grid: Bits<2> = Dense::from_rows(rows.clone(), false, Edges::Fixed);
+        let back: Vec<Vec<bool>> = grid.rows().map(|row| row.collect()).collect();
+        assert_eq!(back, rows);
+        assert_eq!(grid.count(true), 3);
+    }
+}
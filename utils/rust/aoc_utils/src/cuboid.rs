@@ -0,0 +1,238 @@
+//! Axis-aligned boxes in any number of dimensions.
+//!
+//! Like `Range`, bounds are half-open: a `Cuboid` contains the points `p` with
+//! `min[i] <= p[i] < max[i]` for every axis `i`.
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Cuboid<const N: usize> {
+    pub min: [isize; N],
+    pub max: [isize; N],
+}
+
+impl<const N: usize> Cuboid<N> {
+    pub fn new(min: [isize; N], max: [isize; N]) -> Self {
+        Cuboid { min, max }
+    }
+
+    /// The smallest cuboid that contains two opposite corners, both included.
+    pub fn from_corners(a: [isize; N], b: [isize; N]) -> Self {
+        Cuboid {
+            min: std::array::from_fn(|i| a[i].min(b[i])),
+            max: std::array::from_fn(|i| a[i].max(b[i]) + 1),
+        }
+    }
+
+    /// Length of each side
+    pub fn dims(&self) -> [isize; N] {
+        std::array::from_fn(|i| (self.max[i] - self.min[i]).max(0))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        (0..N).any(|i| self.min[i] >= self.max[i])
+    }
+
+    /// Number of points in the cuboid, or `None` if it does not fit in an `isize`
+    pub fn volume(&self) -> Option<isize> {
+        self.dims()
+            .iter()
+            .try_fold(1isize, |acc, &d| acc.checked_mul(d))
+    }
+
+    pub fn contains_point(&self, p: &[isize; N]) -> bool {
+        (0..N).all(|i| self.min[i] <= p[i] && p[i] < self.max[i])
+    }
+
+    /// Whether `other` is completely inside this cuboid
+    pub fn contains(&self, other: &Self) -> bool {
+        other.is_empty()
+            || (0..N).all(|i| self.min[i] <= other.min[i] && other.max[i] <= self.max[i])
+    }
+
+    /// Manhattan distance from `p` to the closest point in the cuboid
+    pub fn distance(&self, p: &[isize; N]) -> isize {
+        (0..N)
+            .map(|i| (self.min[i] - p[i]).max(p[i] - (self.max[i] - 1)).max(0))
+            .sum()
+    }
+
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        let inter = Cuboid {
+            min: std::array::from_fn(|i| self.min[i].max(other.min[i])),
+            max: std::array::from_fn(|i| self.max[i].min(other.max[i])),
+        };
+        (!inter.is_empty()).then_some(inter)
+    }
+
+    /// Disjoint cuboids that cover the points of `self` that are not in `other`
+    pub fn subtract(&self, other: &Self) -> Vec<Self> {
+        let Some(inter) = self.intersection(other) else {
+            return if self.is_empty() { vec![] } else { vec![*self] };
+        };
+        let mut pieces = vec![];
+        let mut rest = *self;
+        // Slice off the parts before and after the intersection, one axis at a time
+        for i in 0..N {
+            if rest.min[i] < inter.min[i] {
+                let mut piece = rest;
+                piece.max[i] = inter.min[i];
+                pieces.push(piece);
+            }
+            if inter.max[i] < rest.max[i] {
+                let mut piece = rest;
+                piece.min[i] = inter.max[i];
+                pieces.push(piece);
+            }
+            rest.min[i] = inter.min[i];
+            rest.max[i] = inter.max[i];
+        }
+        pieces
+    }
+
+    pub fn translate(&self, offset: [isize; N]) -> Self {
+        Cuboid {
+            min: std::array::from_fn(|i| self.min[i] + offset[i]),
+            max: std::array::from_fn(|i| self.max[i] + offset[i]),
+        }
+    }
+
+    /// Cut every side that is longer than one in half, which results in up to `2^N` pieces
+    pub fn split(&self) -> Vec<Self> {
+        let mut pieces = vec![*self];
+        for i in 0..N {
+            if self.max[i] - self.min[i] < 2 {
+                continue;
+            }
+            let mid = self.min[i] + (self.max[i] - self.min[i]) / 2;
+            pieces = pieces
+                .into_iter()
+                .flat_map(|piece| {
+                    let (mut low, mut high) = (piece, piece);
+                    low.max[i] = mid;
+                    high.min[i] = mid;
+                    [low, high]
+                })
+                .collect();
+        }
+        pieces
+    }
+}
+
+/// Number of points covered after turning the points in a sequence of cuboids on or off.
+///
+/// Every change adds the cuboid with a positive sign (if it is turned on), and the intersection
+/// with every cuboid that is already accounted for with the opposite sign, so the total
+/// volume is always the sum of the signed volumes.
+#[derive(Debug, Clone, Default)]
+pub struct SignedVolume<const N: usize> {
+    cuboids: HashMap<Cuboid<N>, isize>,
+}
+
+impl<const N: usize> SignedVolume<N> {
+    pub fn new() -> Self {
+        SignedVolume {
+            cuboids: HashMap::new(),
+        }
+    }
+
+    pub fn set(&mut self, cuboid: &Cuboid<N>, on: bool) {
+        let mut changes: HashMap<Cuboid<N>, isize> = HashMap::new();
+        for (other, sign) in self.cuboids.iter() {
+            if let Some(inter) = other.intersection(cuboid) {
+                *changes.entry(inter).or_default() -= sign;
+            }
+        }
+        if on && !cuboid.is_empty() {
+            *changes.entry(*cuboid).or_default() += 1;
+        }
+        for (c, sign) in changes {
+            let entry = self.cuboids.entry(c).or_default();
+            *entry += sign;
+            if *entry == 0 {
+                self.cuboids.remove(&c);
+            }
+        }
+    }
+
+    pub fn add(&mut self, cuboid: &Cuboid<N>) {
+        self.set(cuboid, true)
+    }
+
+    pub fn remove(&mut self, cuboid: &Cuboid<N>) {
+        self.set(cuboid, false)
+    }
+
+    /// Number of points that are on, or `None` if it does not fit in an `isize`
+    pub fn volume(&self) -> Option<isize> {
+        self.cuboids.iter().try_fold(0isize, |acc, (c, sign)| {
+            acc.checked_add(c.volume()?.checked_mul(*sign)?)
+        })
+    }
+}
+
+impl<'a, const N: usize> Extend<(bool, &'a Cuboid<N>)> for SignedVolume<N> {
+    fn extend<I: IntoIterator<Item = (bool, &'a Cuboid<N>)>>(&mut self, iter: I) {
+        for (on, cuboid) in iter {
+            self.set(cuboid, on);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cuboid() {
+        let a = Cuboid::from_corners([12, 12, 12], [10, 10, 10]);
+        let b = Cuboid::new([11, 11, 11], [14, 14, 14]);
+        assert_eq!(a, Cuboid::new([10, 10, 10], [13, 13, 13]));
+        assert_eq!(a.volume(), Some(27));
+        assert_eq!(a.intersection(&b).and_then(|c| c.volume()), Some(8));
+        assert!(a.overlaps(&b) && !a.overlaps(&b.translate([2, 0, 0])));
+        assert!(a.contains_point(&[12, 10, 11]) && !a.contains_point(&[13, 10, 11]));
+        assert_eq!(a.distance(&[11, 12, 10]), 0);
+        assert_eq!(a.distance(&[0, 15, 11]), 13);
+        assert!(b.contains(&a.intersection(&b).unwrap()) && !b.contains(&a));
+
+        let pieces = a.subtract(&b);
+        assert_eq!(
+            pieces.iter().map(|p| p.volume()).sum::<Option<isize>>(),
+            Some(27 - 8)
+        );
+        for (i, p) in pieces.iter().enumerate() {
+            assert!(a.contains(p) && !p.overlaps(&b));
+            assert!(pieces[i + 1..].iter().all(|q| !p.overlaps(q)));
+        }
+        assert!(a.subtract(&a).is_empty());
+
+        let halves = Cuboid::new([0, 0], [5, 1]).split();
+        assert_eq!(
+            halves,
+            [Cuboid::new([0, 0], [2, 1]), Cuboid::new([2, 0], [5, 1])]
+        );
+        assert_eq!(a.split().len(), 8);
+
+        let huge = Cuboid::new([0; 3], [isize::MAX / 2; 3]);
+        assert_eq!(huge.volume(), None);
+        assert_eq!(huge.dims(), [isize::MAX / 2; 3]);
+    }
+
+    #[test]
+    fn test_signed_volume() {
+        let mut total = SignedVolume::new();
+        total.add(&Cuboid::new([10, 10, 10], [13, 13, 13]));
+        total.add(&Cuboid::new([11, 11, 11], [14, 14, 14]));
+        assert_eq!(total.volume(), Some(46));
+        total.remove(&Cuboid::new([9, 9, 9], [12, 12, 12]));
+        assert_eq!(total.volume(), Some(38));
+        total.add(&Cuboid::from_corners([10, 10, 10], [10, 10, 10]));
+        assert_eq!(total.volume(), Some(39));
+        total.remove(&Cuboid::new([0, 0, 0], [20, 20, 20]));
+        assert_eq!(total.volume(), Some(0));
+    }
+}
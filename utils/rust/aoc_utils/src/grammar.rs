@@ -0,0 +1,327 @@
+//! Context-free grammars, for puzzles with rules like `8: 42 | 42 8` or `H => HO`.
+//!
+//! Rules can be recursive in any way. `Grammar::matches` uses an Earley parser, and
+//! `Grammar::min_steps` uses CYK to find the derivation with the fewest rule applications.
+//! Rules cannot be empty.
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Symbol {
+    /// Matches one token of the input
+    Terminal(String),
+    /// Matches anything the rule with that name matches
+    Rule(String),
+}
+
+/// Symbol with the rule names replaced by their ids
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Sym {
+    T(String),
+    N(usize),
+}
+
+#[derive(Debug, Clone)]
+struct Production {
+    lhs: usize,
+    rhs: Vec<Sym>,
+    cost: usize,
+}
+
+/// Earley item: a production, how many of its symbols have been matched, and where it started
+type Item = (usize, usize, usize);
+
+/// Earley item sets, one for every position of the input
+struct Chart {
+    items: Vec<Vec<Item>>,
+    seen: Vec<HashSet<Item>>,
+}
+
+impl Chart {
+    fn add(&mut self, pos: usize, item: Item) {
+        if self.seen[pos].insert(item) {
+            self.items[pos].push(item);
+        }
+    }
+}
+
+fn relax(costs: &mut HashMap<usize, usize>, symbol: usize, cost: usize) -> bool {
+    match costs.get(&symbol) {
+        Some(&old) if old <= cost => false,
+        _ => {
+            costs.insert(symbol, cost);
+            true
+        }
+    }
+}
+
+/// Split a string into its characters, which is what most grammars use as tokens
+pub fn chars(s: &str) -> Vec<&str> {
+    s.char_indices()
+        .map(|(i, c)| &s[i..i + c.len_utf8()])
+        .collect()
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Grammar {
+    names: Vec<String>,
+    ids: HashMap<String, usize>,
+    productions: Vec<Production>,
+}
+
+impl Grammar {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Load rules like `0: 4 1 | 1 4` or `4: "a"`, one per line. Names do not need to be
+    /// numbers.
+    pub fn from_rules(text: &str) -> Result<Self, String> {
+        let mut grammar = Grammar::new();
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let (name, alternatives) = line
+                .split_once(':')
+                .ok_or_else(|| format!("invalid rule: {}", line))?;
+            for alternative in alternatives.split('|') {
+                let rhs: Vec<Symbol> = alternative
+                    .split_whitespace()
+                    .map(|tok| {
+                        if tok.len() >= 2 && tok.starts_with('"') && tok.ends_with('"') {
+                            Symbol::Terminal(tok[1..tok.len() - 1].to_string())
+                        } else {
+                            Symbol::Rule(tok.to_string())
+                        }
+                    })
+                    .collect();
+                if rhs.is_empty() {
+                    return Err(format!("empty alternative in rule: {}", line));
+                }
+                grammar.add_rule(name.trim(), &rhs);
+            }
+        }
+        Ok(grammar)
+    }
+
+    fn id(&mut self, name: &str) -> usize {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+        self.names.push(name.to_string());
+        self.ids.insert(name.to_string(), self.names.len() - 1);
+        self.names.len() - 1
+    }
+
+    /// Add an alternative to a rule
+    pub fn add_rule(&mut self, name: &str, rhs: &[Symbol]) {
+        self.add_rule_with_cost(name, rhs, 1);
+    }
+
+    /// Add an alternative to a rule, which counts as `cost` steps in `min_steps`
+    pub fn add_rule_with_cost(&mut self, name: &str, rhs: &[Symbol], cost: usize) {
+        assert!(!rhs.is_empty(), "empty rules are not supported");
+        let lhs = self.id(name);
+        let rhs = rhs
+            .iter()
+            .map(|s| match s {
+                Symbol::Terminal(t) => Sym::T(t.clone()),
+                Symbol::Rule(r) => Sym::N(self.id(r)),
+            })
+            .collect();
+        self.productions.push(Production { lhs, rhs, cost });
+    }
+
+    fn by_lhs(&self) -> Vec<Vec<usize>> {
+        let mut by_lhs = vec![vec![]; self.names.len()];
+        for (p, production) in self.productions.iter().enumerate() {
+            by_lhs[production.lhs].push(p);
+        }
+        by_lhs
+    }
+
+    /// Whether the rule `start` matches the whole input
+    pub fn matches<T: AsRef<str>>(&self, start: &str, tokens: &[T]) -> bool {
+        let start = match self.ids.get(start) {
+            Some(&id) => id,
+            None => return false,
+        };
+        let by_lhs = self.by_lhs();
+        let n = tokens.len();
+        let mut chart = Chart {
+            items: vec![vec![]; n + 1],
+            seen: vec![HashSet::new(); n + 1],
+        };
+        for &p in &by_lhs[start] {
+            chart.add(0, (p, 0, 0));
+        }
+        for pos in 0..=n {
+            let mut k = 0;
+            while k < chart.items[pos].len() {
+                let (p, dot, origin) = chart.items[pos][k];
+                k += 1;
+                match self.productions[p].rhs.get(dot) {
+                    // Complete: advance every item that was waiting for this rule. Rules are
+                    // not empty, so `origin` is an earlier position that will not change.
+                    None => {
+                        let lhs = Sym::N(self.productions[p].lhs);
+                        let waiting: Vec<Item> = chart.items[origin]
+                            .iter()
+                            .filter(|&&(q, d, _)| self.productions[q].rhs.get(d) == Some(&lhs))
+                            .map(|&(q, d, o)| (q, d + 1, o))
+                            .collect();
+                        for item in waiting {
+                            chart.add(pos, item);
+                        }
+                    }
+                    // Predict
+                    Some(Sym::N(rule)) => {
+                        for &q in &by_lhs[*rule] {
+                            chart.add(pos, (q, 0, pos));
+                        }
+                    }
+                    // Scan
+                    Some(Sym::T(t)) => {
+                        if tokens.get(pos).map(|tok| tok.as_ref()) == Some(t.as_str()) {
+                            chart.add(pos + 1, (p, dot + 1, origin));
+                        }
+                    }
+                }
+            }
+        }
+        chart.items[n].iter().any(|&(p, dot, origin)| {
+            let production = &self.productions[p];
+            origin == 0 && production.lhs == start && dot == production.rhs.len()
+        })
+    }
+
+    /// Fewest steps (the sum of the cost of every rule applied) to derive the input from the
+    /// rule `start`, if it matches
+    pub fn min_steps<T: AsRef<str>>(&self, start: &str, tokens: &[T]) -> Option<usize> {
+        let start = *self.ids.get(start)?;
+        let n = tokens.len();
+        if n == 0 {
+            return None;
+        }
+        // Symbols in the table: rules, then terminals, then the rest of each long production,
+        // so that every production has one or two symbols:
+        //   A -> B C D  becomes  A -> B X, X -> C D (and X costs nothing)
+        let mut count = self.names.len();
+        let mut terminals: HashMap<&str, usize> = HashMap::new();
+        let mut units = vec![];
+        let mut binary = vec![];
+        for production in &self.productions {
+            let ids: Vec<usize> = production
+                .rhs
+                .iter()
+                .map(|s| match s {
+                    Sym::N(id) => *id,
+                    Sym::T(t) => *terminals.entry(t.as_str()).or_insert_with(|| {
+                        count += 1;
+                        count - 1
+                    }),
+                })
+                .collect();
+            if ids.len() == 1 {
+                units.push((production.lhs, ids[0], production.cost));
+                continue;
+            }
+            let (mut lhs, mut cost) = (production.lhs, production.cost);
+            for &first in &ids[..ids.len() - 2] {
+                binary.push((first, lhs, count, cost));
+                lhs = count;
+                cost = 0;
+                count += 1;
+            }
+            binary.push((ids[ids.len() - 2], lhs, ids[ids.len() - 1], cost));
+        }
+        let mut by_left = vec![vec![]; count];
+        for (left, lhs, right, cost) in binary {
+            by_left[left].push((lhs, right, cost));
+        }
+
+        // Cost of every symbol that matches the tokens `i..i + len`, at `i * n + len - 1`
+        let mut table: Vec<HashMap<usize, usize>> = vec![HashMap::new(); n * n];
+        for len in 1..=n {
+            for i in 0..=n - len {
+                let mut costs = HashMap::new();
+                if len == 1 {
+                    if let Some(&t) = terminals.get(tokens[i].as_ref()) {
+                        costs.insert(t, 0);
+                    }
+                }
+                for split in 1..len {
+                    let left = &table[i * n + split - 1];
+                    let right = &table[(i + split) * n + len - split - 1];
+                    for (&symbol, &left_cost) in left {
+                        for &(lhs, other, cost) in &by_left[symbol] {
+                            if let Some(&right_cost) = right.get(&other) {
+                                relax(&mut costs, lhs, left_cost + right_cost + cost);
+                            }
+                        }
+                    }
+                }
+                let mut changed = true;
+                while changed {
+                    changed = false;
+                    for &(lhs, symbol, cost) in &units {
+                        if let Some(&c) = costs.get(&symbol) {
+                            changed |= relax(&mut costs, lhs, c + cost);
+                        }
+                    }
+                }
+                table[i * n + len - 1] = costs;
+            }
+        }
+        table[n - 1].get(&start).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        let grammar = Grammar::from_rules(
+            "0: 8 11
+8: 42 | 42 8
+11: 42 31 | 42 11 31
+42: \"a\"
+31: \"b\"",
+        )
+        .unwrap();
+        for valid in &["aab", "aaab", "aaabb", "aaaaabbb"] {
+            assert!(grammar.matches("0", &chars(valid)), "{}", valid);
+        }
+        for invalid in &["", "ab", "aabb", "aaba", "ba", "aaabbc"] {
+            assert!(!grammar.matches("0", &chars(invalid)), "{}", invalid);
+        }
+        assert!(!grammar.matches("missing", &chars("ab")));
+        assert_eq!(grammar.min_steps("0", &chars("aaabb")), Some(9));
+        assert!(Grammar::from_rules("0: 1 | ").is_err());
+    }
+
+    #[test]
+    fn test_min_steps() {
+        let mut grammar = Grammar::new();
+        for (from, to) in &[
+            ("e", "H"),
+            ("e", "O"),
+            ("H", "HO"),
+            ("H", "OH"),
+            ("O", "HH"),
+        ] {
+            let rhs: Vec<Symbol> = chars(to)
+                .into_iter()
+                .map(|s| Symbol::Rule(s.to_string()))
+                .collect();
+            grammar.add_rule(from, &rhs);
+        }
+        for element in &["H", "O"] {
+            grammar.add_rule_with_cost(element, &[Symbol::Terminal(element.to_string())], 0);
+        }
+        assert_eq!(grammar.min_steps("e", &chars("HOH")), Some(3));
+        assert_eq!(grammar.min_steps("e", &chars("HOHOHO")), Some(6));
+        assert_eq!(grammar.min_steps("H", &chars("H")), Some(0));
+        assert_eq!(grammar.min_steps("e", &chars("HX")), None);
+        assert!(grammar.matches("e", &chars("HOHOHO")));
+    }
+}
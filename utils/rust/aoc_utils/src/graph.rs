@@ -0,0 +1,664 @@
+//! Graph algorithms over adjacency maps (`node -> neighbor -> weight`), and over unweighted
+//! graphs of integer ids (`SimpleGraph`), with an `Interner` to map labels to ids.
+//!
+//! Nodes are sorted before running any algorithm, so results do not depend on the iteration
+//! order of the maps.
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, BinaryHeap, HashMap};
+use std::hash::Hash;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+/// Graph with weighted edges. In undirected graphs, every edge should appear in both
+/// directions.
+pub type Graph<N> = HashMap<N, HashMap<N, usize>>;
+
+/// The result of splitting a graph in two
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cut<N> {
+    /// Total weight of the edges between both sides
+    pub weight: usize,
+    pub sides: [Vec<N>; 2],
+    /// Edges that cross the cut, from the first side to the second
+    pub edges: Vec<(N, N)>,
+}
+
+impl<N> Cut<N> {
+    pub fn sizes(&self) -> [usize; 2] {
+        [self.sides[0].len(), self.sides[1].len()]
+    }
+}
+
+/// Sorted list of nodes, and the edges between their indices
+fn index<N: Copy + Ord + Hash>(graph: &Graph<N>) -> (Vec<N>, Vec<HashMap<usize, usize>>) {
+    let mut nodes: Vec<N> = graph
+        .iter()
+        .flat_map(|(n, neighbors)| std::iter::once(n).chain(neighbors.keys()))
+        .copied()
+        .collect();
+    nodes.sort_unstable();
+    nodes.dedup();
+    let ids: HashMap<N, usize> = nodes.iter().enumerate().map(|(i, &n)| (n, i)).collect();
+    let mut adjacency = vec![HashMap::new(); nodes.len()];
+    for (n, neighbors) in graph {
+        for (m, &weight) in neighbors {
+            if n != m {
+                adjacency[ids[n]].insert(ids[m], weight);
+            }
+        }
+    }
+    (nodes, adjacency)
+}
+
+/// Global minimum cut, using the Stoer-Wagner algorithm.
+///
+/// Returns `None` if the graph has fewer than two nodes.
+pub fn min_cut<N: Copy + Ord + Hash>(graph: &Graph<N>) -> Option<Cut<N>> {
+    let (nodes, mut adjacency) = index(graph);
+    let n = nodes.len();
+    if n < 2 {
+        return None;
+    }
+    let original = adjacency.clone();
+    // Nodes that have been merged into each node
+    let mut groups: Vec<Vec<usize>> = (0..n).map(|i| vec![i]).collect();
+    let mut active: Vec<usize> = (0..n).collect();
+    let mut best: Option<(usize, Vec<usize>)> = None;
+
+    while active.len() > 1 {
+        // Add nodes one by one, picking the one most tightly connected to the ones added so far
+        let mut weight = vec![0; n];
+        let mut added = vec![false; n];
+        let mut heap: BinaryHeap<(usize, Reverse<usize>)> =
+            active.iter().map(|&i| (0, Reverse(i))).collect();
+        let mut order = Vec::with_capacity(active.len());
+        while let Some((w, Reverse(v))) = heap.pop() {
+            if added[v] || w != weight[v] {
+                continue;
+            }
+            added[v] = true;
+            order.push(v);
+            for (&u, &c) in &adjacency[v] {
+                if !added[u] {
+                    weight[u] += c;
+                    heap.push((weight[u], Reverse(u)));
+                }
+            }
+        }
+        let t = order.pop().unwrap();
+        let s = order.pop().unwrap();
+        // The cut of the phase separates the last node from the rest
+        if best.as_ref().is_none_or(|(w, _)| weight[t] < *w) {
+            best = Some((weight[t], groups[t].clone()));
+        }
+
+        // Merge t into s
+        for (u, c) in std::mem::take(&mut adjacency[t]) {
+            adjacency[u].remove(&t);
+            if u != s {
+                *adjacency[s].entry(u).or_default() += c;
+                *adjacency[u].entry(s).or_default() += c;
+            }
+        }
+        let merged = std::mem::take(&mut groups[t]);
+        groups[s].extend(merged);
+        active.retain(|&i| i != t);
+    }
+
+    let (weight, side) = best?;
+    let mut in_side = vec![false; n];
+    for &i in &side {
+        in_side[i] = true;
+    }
+    let mut edges: Vec<(N, N)> = side
+        .iter()
+        .flat_map(|&i| {
+            original[i]
+                .keys()
+                .filter(|&&j| !in_side[j])
+                .map(move |&j| (i, j))
+        })
+        .map(|(i, j)| (nodes[i], nodes[j]))
+        .collect();
+    edges.sort_unstable();
+    let (first, second): (Vec<usize>, Vec<usize>) = (0..n).partition(|&i| in_side[i]);
+    Some(Cut {
+        weight,
+        sides: [
+            first.into_iter().map(|i| nodes[i]).collect(),
+            second.into_iter().map(|i| nodes[i]).collect(),
+        ],
+        edges,
+    })
+}
+
+/// Shrink a graph (e.g. the tiles of a maze) to the junctions reachable from `start`, with an
+/// edge for every corridor between two junctions, weighted by its length.
+///
+/// `successors` gives the nodes that can be reached in one step, which may not include every
+/// neighbor (e.g. one-way slopes), so the result can be directed. `start` is always a
+/// junction. Corridors that reach a dead end are dropped, and only the longest of several
+/// corridors between the same two junctions is kept.
+pub fn compress<N, I>(
+    start: N,
+    is_junction: impl Fn(N) -> bool,
+    successors: impl Fn(N) -> I,
+) -> Graph<N>
+where
+    N: Copy + Eq + Hash,
+    I: IntoIterator<Item = N>,
+{
+    let mut graph: Graph<N> = HashMap::new();
+    let mut pending = vec![start];
+    while let Some(origin) = pending.pop() {
+        if graph.contains_key(&origin) {
+            continue;
+        }
+        let edges = graph.entry(origin).or_default();
+        for first in successors(origin) {
+            let (mut prev, mut pos, mut len) = (origin, first, 1);
+            loop {
+                if pos == start || is_junction(pos) {
+                    let weight = edges.entry(pos).or_default();
+                    *weight = (*weight).max(len);
+                    pending.push(pos);
+                    break;
+                }
+                let Some(next) = successors(pos).into_iter().find(|&n| n != prev) else {
+                    break;
+                };
+                (prev, pos, len) = (pos, next, len + 1);
+            }
+        }
+    }
+    graph
+}
+
+/// Depth-first search for `longest_path`, over the indices of the nodes
+struct LongestPath<'a> {
+    edges: &'a [Vec<(usize, usize)>],
+    /// Heaviest edge out of every node, to bound the length that is left
+    heaviest: &'a [usize],
+    end: usize,
+    /// Length of the longest path found so far, plus one (zero if none was found)
+    best: &'a AtomicUsize,
+}
+
+impl LongestPath<'_> {
+    fn search(&self, node: usize, visited: u128, len: usize, left: usize) {
+        if node == self.end {
+            self.best.fetch_max(len + 1, Ordering::Relaxed);
+            return;
+        }
+        // Every edge still to be taken leaves a different node that has not been visited yet
+        if len + self.heaviest[node] + left < self.best.load(Ordering::Relaxed) {
+            return;
+        }
+        for &(next, weight) in &self.edges[node] {
+            if visited & (1 << next) == 0 {
+                let left = left - self.heaviest[next];
+                self.search(next, visited | (1 << next), len + weight, left);
+            }
+        }
+    }
+}
+
+/// Length of the longest path from `start` to `end` that does not visit any node twice, or
+/// `None` if `end` cannot be reached.
+///
+/// The search is exhaustive, so it is only feasible on small graphs (e.g. after `compress`).
+/// Graphs with more than 128 nodes are not supported, and also return `None`. The first
+/// levels of the search are expanded until there are enough partial paths to keep every
+/// thread busy, and those are then searched in parallel.
+pub fn longest_path<N: Copy + Ord + Hash>(graph: &Graph<N>, start: N, end: N) -> Option<usize> {
+    if start == end {
+        return Some(0);
+    }
+    let (nodes, adjacency) = index(graph);
+    if nodes.len() > 128 {
+        return None;
+    }
+    let id = |n: N| nodes.binary_search(&n).ok();
+    let (start, end) = (id(start)?, id(end)?);
+    let edges: Vec<Vec<(usize, usize)>> = adjacency
+        .into_iter()
+        .map(|neighbors| {
+            let mut neighbors: Vec<(usize, usize)> = neighbors.into_iter().collect();
+            // Trying the heaviest edges first finds long paths sooner, which prunes more
+            neighbors.sort_unstable_by_key(|&(n, w)| (Reverse(w), n));
+            neighbors
+        })
+        .collect();
+    let heaviest: Vec<usize> = edges
+        .iter()
+        .map(|e| e.first().map_or(0, |&(_, w)| w))
+        .collect();
+    let left = heaviest.iter().sum::<usize>() - heaviest[start];
+
+    let best = AtomicUsize::new(0);
+    let search = LongestPath {
+        edges: &edges,
+        heaviest: &heaviest,
+        end,
+        best: &best,
+    };
+    // Partial paths as (last node, visited nodes, length, heaviest edges left). Even if there
+    // is a single edge out of `start`, the paths branch out after a few levels.
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let mut frontier = vec![(start, 1u128 << start, 0, left)];
+    while !frontier.is_empty() && frontier.len() < 4 * threads {
+        let mut next_level = vec![];
+        for (node, visited, len, left) in frontier {
+            if node == end {
+                best.fetch_max(len + 1, Ordering::Relaxed);
+                continue;
+            }
+            for &(next, weight) in &edges[node] {
+                if visited & (1 << next) == 0 {
+                    let left = left - heaviest[next];
+                    next_level.push((next, visited | (1 << next), len + weight, left));
+                }
+            }
+        }
+        frontier = next_level;
+    }
+
+    let queue = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        for _ in 0..threads.min(frontier.len()) {
+            scope.spawn(|| {
+                while let Some(&(node, visited, len, left)) =
+                    frontier.get(queue.fetch_add(1, Ordering::Relaxed))
+                {
+                    search.search(node, visited, len, left);
+                }
+            });
+        }
+    });
+    best.into_inner().checked_sub(1)
+}
+
+/// Assigns consecutive ids to labels, in order of appearance
+#[derive(Debug, Clone)]
+pub struct Interner<T> {
+    ids: HashMap<T, usize>,
+    labels: Vec<T>,
+}
+
+impl<T> Default for Interner<T> {
+    fn default() -> Self {
+        Interner {
+            ids: HashMap::new(),
+            labels: vec![],
+        }
+    }
+}
+
+impl<T: Hash + Eq + Clone> Interner<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The id of `label`, which gets a new id if it has not been seen before
+    pub fn intern(&mut self, label: T) -> usize {
+        if let Some(&id) = self.ids.get(&label) {
+            return id;
+        }
+        self.labels.push(label.clone());
+        self.ids.insert(label, self.labels.len() - 1);
+        self.labels.len() - 1
+    }
+
+    pub fn id(&self, label: &T) -> Option<usize> {
+        self.ids.get(label).copied()
+    }
+
+    pub fn label(&self, id: usize) -> &T {
+        &self.labels[id]
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+}
+
+/// Undirected graph without weights, loops or repeated edges, with nodes `0..n`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SimpleGraph {
+    adjacency: Vec<BTreeSet<usize>>,
+}
+
+impl SimpleGraph {
+    pub fn new(n: usize) -> Self {
+        SimpleGraph {
+            adjacency: vec![BTreeSet::new(); n],
+        }
+    }
+
+    /// Build a graph from a list of edges between labels, and the ids given to each label
+    pub fn from_labelled_edges<T: Hash + Eq + Clone>(
+        edges: impl IntoIterator<Item = (T, T)>,
+    ) -> (Self, Interner<T>) {
+        let mut labels = Interner::new();
+        let mut graph = SimpleGraph::default();
+        for (a, b) in edges {
+            let (a, b) = (labels.intern(a), labels.intern(b));
+            graph.add_edge(a, b);
+        }
+        (graph, labels)
+    }
+
+    /// Add an edge, and any node that did not exist yet
+    pub fn add_edge(&mut self, a: usize, b: usize) {
+        if self.adjacency.len() <= a.max(b) {
+            self.adjacency.resize(a.max(b) + 1, BTreeSet::new());
+        }
+        if a != b {
+            self.adjacency[a].insert(b);
+            self.adjacency[b].insert(a);
+        }
+    }
+
+    pub fn has_edge(&self, a: usize, b: usize) -> bool {
+        self.adjacency.get(a).is_some_and(|n| n.contains(&b))
+    }
+
+    pub fn neighbors(&self, node: usize) -> &BTreeSet<usize> {
+        &self.adjacency[node]
+    }
+
+    /// Number of nodes
+    pub fn len(&self) -> usize {
+        self.adjacency.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.adjacency.is_empty()
+    }
+
+    /// Nodes in the order they are removed when removing a node with the fewest remaining
+    /// neighbors each time. Every node has at most `degeneracy` neighbors later in the order.
+    pub fn degeneracy_order(&self) -> Vec<usize> {
+        let mut degree: Vec<usize> = self.adjacency.iter().map(|n| n.len()).collect();
+        let mut queue: BTreeSet<(usize, usize)> =
+            degree.iter().enumerate().map(|(v, &d)| (d, v)).collect();
+        let mut removed = vec![false; self.len()];
+        let mut order = Vec::with_capacity(self.len());
+        while let Some((_, v)) = queue.pop_first() {
+            removed[v] = true;
+            order.push(v);
+            for &u in &self.adjacency[v] {
+                if !removed[u] {
+                    queue.remove(&(degree[u], u));
+                    degree[u] -= 1;
+                    queue.insert((degree[u], u));
+                }
+            }
+        }
+        order
+    }
+
+    /// Bron-Kerbosch with pivoting: report every maximal clique that extends `clique` with
+    /// nodes from `candidates`, and no nodes from `excluded`
+    fn bron_kerbosch(
+        &self,
+        clique: &mut Vec<usize>,
+        mut candidates: BTreeSet<usize>,
+        mut excluded: BTreeSet<usize>,
+        report: &mut impl FnMut(&[usize]),
+    ) {
+        let Some(pivot) = candidates
+            .union(&excluded)
+            .max_by_key(|&&u| self.adjacency[u].intersection(&candidates).count())
+            .copied()
+        else {
+            report(clique);
+            return;
+        };
+        // Every maximal clique contains the pivot or one of its non-neighbors
+        let branches: Vec<usize> = candidates
+            .difference(&self.adjacency[pivot])
+            .copied()
+            .collect();
+        for v in branches {
+            let neighbors = &self.adjacency[v];
+            clique.push(v);
+            self.bron_kerbosch(
+                clique,
+                candidates.intersection(neighbors).copied().collect(),
+                excluded.intersection(neighbors).copied().collect(),
+                report,
+            );
+            clique.pop();
+            candidates.remove(&v);
+            excluded.insert(v);
+        }
+    }
+
+    /// Call `f` with every maximal clique (not contained in a larger one)
+    pub fn for_each_maximal_clique(&self, mut f: impl FnMut(&[usize])) {
+        let order = self.degeneracy_order();
+        let mut rank = vec![0; self.len()];
+        for (i, &v) in order.iter().enumerate() {
+            rank[v] = i;
+        }
+        // Following the degeneracy order keeps the candidate sets small
+        for &v in &order {
+            let (later, earlier): (BTreeSet<usize>, BTreeSet<usize>) =
+                self.adjacency[v].iter().partition(|&&u| rank[u] > rank[v]);
+            self.bron_kerbosch(&mut vec![v], later, earlier, &mut f);
+        }
+    }
+
+    /// Every maximal clique, with its nodes sorted
+    pub fn maximal_cliques(&self) -> Vec<Vec<usize>> {
+        let mut cliques = vec![];
+        self.for_each_maximal_clique(|clique| {
+            let mut clique = clique.to_vec();
+            clique.sort_unstable();
+            cliques.push(clique);
+        });
+        cliques.sort_unstable();
+        cliques
+    }
+
+    /// A clique with as many nodes as possible, sorted. If there are several, the
+    /// lexicographically smallest one.
+    pub fn max_clique(&self) -> Vec<usize> {
+        self.maximal_cliques()
+            .into_iter()
+            .max_by(|a, b| a.len().cmp(&b.len()).then_with(|| b.cmp(a)))
+            .unwrap_or_default()
+    }
+
+    /// Every clique with exactly `k` nodes, with its nodes sorted
+    pub fn cliques(&self, k: usize) -> Vec<Vec<usize>> {
+        let mut cliques = vec![];
+        self.extend_clique(&mut vec![], (0..self.len()).collect(), k, &mut cliques);
+        cliques
+    }
+
+    fn extend_clique(
+        &self,
+        clique: &mut Vec<usize>,
+        candidates: Vec<usize>,
+        k: usize,
+        cliques: &mut Vec<Vec<usize>>,
+    ) {
+        if clique.len() == k {
+            cliques.push(clique.clone());
+            return;
+        }
+        for (ix, &v) in candidates.iter().enumerate() {
+            // Only add nodes in increasing order, so every clique is found once
+            let next: Vec<usize> = candidates[ix + 1..]
+                .iter()
+                .copied()
+                .filter(|u| self.adjacency[v].contains(u))
+                .collect();
+            if clique.len() + 1 + next.len() < k {
+                continue;
+            }
+            clique.push(v);
+            self.extend_clique(clique, next, k, cliques);
+            clique.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(edges: &[(u8, u8, usize)]) -> Graph<u8> {
+        let mut g = Graph::new();
+        for &(a, b, w) in edges {
+            g.entry(a).or_default().insert(b, w);
+            g.entry(b).or_default().insert(a, w);
+        }
+        g
+    }
+
+    #[test]
+    fn test_min_cut() {
+        // Example from the Stoer-Wagner paper
+        let g = graph(&[
+            (1, 2, 2),
+            (1, 5, 3),
+            (2, 3, 3),
+            (2, 5, 2),
+            (2, 6, 2),
+            (3, 4, 4),
+            (3, 7, 2),
+            (4, 7, 2),
+            (4, 8, 2),
+            (5, 6, 3),
+            (6, 7, 1),
+            (7, 8, 3),
+        ]);
+        let cut = min_cut(&g).unwrap();
+        assert_eq!(cut.weight, 4);
+        assert_eq!(cut.sizes(), [4, 4]);
+        let mut sides = cut.sides.clone();
+        sides.sort();
+        assert_eq!(sides, [vec![1, 2, 5, 6], vec![3, 4, 7, 8]]);
+        assert_eq!(cut.edges.iter().map(|&(a, b)| g[&a][&b]).sum::<usize>(), 4);
+
+        let disconnected = graph(&[(1, 2, 1), (3, 4, 1)]);
+        let cut = min_cut(&disconnected).unwrap();
+        assert_eq!(cut.weight, 0);
+        assert!(cut.edges.is_empty());
+        assert!(min_cut(&graph(&[])).is_none());
+    }
+
+    #[test]
+    fn test_cliques() {
+        let edges = [
+            ("a", "b"),
+            ("a", "c"),
+            ("b", "c"),
+            ("b", "d"),
+            ("c", "d"),
+            ("d", "e"),
+            ("b", "e"),
+            ("c", "e"),
+            ("e", "f"),
+        ];
+        let (g, labels) = SimpleGraph::from_labelled_edges(edges);
+        assert_eq!(g.len(), 6);
+        assert_eq!(labels.id(&"d"), Some(3));
+        assert_eq!(labels.label(5), &"f");
+        assert!(g.has_edge(4, 5) && !g.has_edge(0, 5));
+
+        let order = g.degeneracy_order();
+        assert_eq!(order.len(), 6);
+        assert_eq!(order[0], 5);
+
+        let names =
+            |clique: &[usize]| -> Vec<&str> { clique.iter().map(|&i| *labels.label(i)).collect() };
+        assert_eq!(names(&g.max_clique()), ["b", "c", "d", "e"]);
+        let maximal: Vec<Vec<&str>> = g.maximal_cliques().iter().map(|c| names(c)).collect();
+        assert_eq!(
+            maximal,
+            [
+                vec!["a", "b", "c"],
+                vec!["b", "c", "d", "e"],
+                vec!["e", "f"]
+            ]
+        );
+        assert_eq!(g.cliques(3).len(), 5);
+        assert_eq!(g.cliques(4).len(), 1);
+        assert!(g.cliques(5).is_empty());
+        assert_eq!(g.cliques(1).len(), 6);
+        assert!(SimpleGraph::default().max_clique().is_empty());
+    }
+
+    #[test]
+    fn test_longest_path() {
+        // Two corridors between the same junctions, one of them with a one-way slope
+        let maze = ["#.#####", "#.....#", "#.###.#", "#...>.#", "#####.#"];
+        let tile = |(r, c): (usize, usize)| maze[r].as_bytes()[c];
+        let successors = |(r, c): (usize, usize)| {
+            let next = match tile((r, c)) {
+                b'>' => vec![(r, c + 1)],
+                _ => vec![(r + 1, c), (r, c + 1), (r, c - 1), (r.wrapping_sub(1), c)],
+            };
+            next.into_iter()
+                .filter(move |&(r, c)| r < maze.len() && tile((r, c)) != b'#')
+        };
+        let (start, end) = ((0, 1), (4, 5));
+        let is_junction = |p| p == end || successors(p).count() > 2;
+        let graph = compress(start, is_junction, successors);
+        assert_eq!(graph.len(), 4);
+        assert_eq!(graph[&(1, 1)][&(3, 5)], 6);
+        assert_eq!(longest_path(&graph, start, end), Some(8));
+        assert_eq!(longest_path(&graph, end, start), Some(8));
+        assert_eq!(longest_path(&graph, start, start), Some(0));
+
+        let mut graph: Graph<char> = HashMap::new();
+        for (a, b, w) in [
+            ('a', 'b', 1),
+            ('b', 'd', 5),
+            ('a', 'c', 2),
+            ('c', 'd', 1),
+            ('b', 'c', 10),
+        ] {
+            graph.entry(a).or_default().insert(b, w);
+            graph.entry(b).or_default().insert(a, w);
+        }
+        graph.entry('e').or_default();
+        assert_eq!(longest_path(&graph, 'a', 'd'), Some(17));
+        assert_eq!(longest_path(&graph, 'a', 'e'), None);
+        assert_eq!(longest_path(&graph, 'a', 'z'), None);
+
+        // A single edge out of the start, so the search has to branch out later
+        let mut grid: Graph<(usize, usize)> = HashMap::new();
+        for r in 0..4 {
+            for c in 0..4 {
+                for next in [(r + 1, c), (r, c + 1)] {
+                    if next.0 < 4 && next.1 < 4 {
+                        grid.entry((r, c)).or_default().insert(next, 1);
+                        grid.entry(next).or_default().insert((r, c), 1);
+                    }
+                }
+            }
+        }
+        grid.entry((9, 9)).or_default().insert((0, 0), 1);
+        grid.entry((0, 0)).or_default().insert((9, 9), 1);
+        assert_eq!(longest_path(&grid, (9, 9), (3, 3)), Some(15));
+
+        let mut line: Graph<usize> = HashMap::new();
+        for n in 0..128 {
+            line.entry(n).or_default().insert(n + 1, 1);
+            line.entry(n + 1).or_default().insert(n, 1);
+        }
+        assert_eq!(longest_path(&line, 0, 127), None);
+        line.remove(&128);
+        line.get_mut(&127).unwrap().remove(&128);
+        assert_eq!(longest_path(&line, 0, 127), Some(127));
+    }
+}
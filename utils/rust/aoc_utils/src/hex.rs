@@ -0,0 +1,267 @@
+//! Coordinates on a grid of hexagons.
+//!
+//! `Hex` uses axial coordinates `(q, r)`. The cube representation adds a third coordinate
+//! `s = -q - r`, which makes distances and rotations symmetric. Hexagons are "pointy", so the
+//! six directions are `e`, `se`, `sw`, `w`, `nw` and `ne`.
+use std::fmt;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Direction {
+    E,
+    SE,
+    SW,
+    W,
+    NW,
+    NE,
+}
+
+impl Direction {
+    /// All directions, clockwise starting from east
+    pub const ALL: [Direction; 6] = [
+        Direction::E,
+        Direction::SE,
+        Direction::SW,
+        Direction::W,
+        Direction::NW,
+        Direction::NE,
+    ];
+
+    pub fn offset(self) -> Hex {
+        match self {
+            Direction::E => Hex::new(1, 0),
+            Direction::SE => Hex::new(0, 1),
+            Direction::SW => Hex::new(-1, 1),
+            Direction::W => Hex::new(-1, 0),
+            Direction::NW => Hex::new(0, -1),
+            Direction::NE => Hex::new(1, -1),
+        }
+    }
+
+    /// Turn clockwise `steps` times 60 degrees (counter-clockwise if negative)
+    pub fn rotate(self, steps: isize) -> Direction {
+        Direction::ALL[(self as isize + steps).rem_euclid(6) as usize]
+    }
+
+    pub fn opposite(self) -> Direction {
+        self.rotate(3)
+    }
+}
+
+impl FromStr for Direction {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "e" => Direction::E,
+            "se" => Direction::SE,
+            "sw" => Direction::SW,
+            "w" => Direction::W,
+            "nw" => Direction::NW,
+            "ne" => Direction::NE,
+            _ => return Err(format!("invalid direction {:?}", s)),
+        })
+    }
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Direction::E => "e",
+            Direction::SE => "se",
+            Direction::SW => "sw",
+            Direction::W => "w",
+            Direction::NW => "nw",
+            Direction::NE => "ne",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Parse a list of directions without separators, e.g. `esenee`
+pub fn parse_directions(s: &str) -> Result<Vec<Direction>, String> {
+    let mut dirs = vec![];
+    let mut rest = s.trim();
+    while !rest.is_empty() {
+        let len = if rest.starts_with('n') || rest.starts_with('s') {
+            2
+        } else {
+            1
+        };
+        let token = rest
+            .get(..len)
+            .ok_or_else(|| format!("incomplete direction {:?}", rest))?;
+        dirs.push(token.parse()?);
+        rest = &rest[len..];
+    }
+    Ok(dirs)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Hex {
+    pub q: isize,
+    pub r: isize,
+}
+
+impl Hex {
+    pub const ORIGIN: Hex = Hex { q: 0, r: 0 };
+
+    pub fn new(q: isize, r: isize) -> Self {
+        Hex { q, r }
+    }
+
+    pub fn from_cube([q, r, s]: [isize; 3]) -> Self {
+        debug_assert_eq!(q + r + s, 0, "invalid cube coordinates");
+        Hex { q, r }
+    }
+
+    pub fn s(self) -> isize {
+        -self.q - self.r
+    }
+
+    pub fn cube(self) -> [isize; 3] {
+        [self.q, self.r, self.s()]
+    }
+
+    pub fn neighbor(self, dir: Direction) -> Hex {
+        self + dir.offset()
+    }
+
+    pub fn neighbors(self) -> impl Iterator<Item = Hex> {
+        Direction::ALL.iter().map(move |&dir| self.neighbor(dir))
+    }
+
+    /// Follow a path of directions
+    pub fn walk(self, path: impl IntoIterator<Item = Direction>) -> Hex {
+        path.into_iter().fold(self, Hex::neighbor)
+    }
+
+    /// Minimum number of steps to get to `other`
+    pub fn distance(self, other: Hex) -> usize {
+        let [q, r, s] = (other - self).cube();
+        ((q.abs() + r.abs() + s.abs()) / 2) as usize
+    }
+
+    /// Hexes at exactly `radius` steps, clockwise starting from the east-most corner
+    pub fn ring(self, radius: usize) -> Vec<Hex> {
+        if radius == 0 {
+            return vec![self];
+        }
+        let mut hex = self + Direction::E.offset() * radius as isize;
+        let mut ring = Vec::with_capacity(6 * radius);
+        // Walking from the east corner, each side goes two turns clockwise from the corner
+        for &dir in Direction::ALL.iter() {
+            for _ in 0..radius {
+                ring.push(hex);
+                hex = hex.neighbor(dir.rotate(2));
+            }
+        }
+        ring
+    }
+
+    /// Hexes at `radius` steps or fewer, ring by ring
+    pub fn within(self, radius: usize) -> impl Iterator<Item = Hex> {
+        (0..=radius).flat_map(move |r| self.ring(r))
+    }
+
+    /// Rotate around the origin, clockwise `steps` times 60 degrees (counter-clockwise if
+    /// negative)
+    pub fn rotate(self, steps: isize) -> Hex {
+        let [mut q, mut r, mut s] = self.cube();
+        for _ in 0..steps.rem_euclid(6) {
+            let prev = [q, r, s];
+            q = -prev[1];
+            r = -prev[2];
+            s = -prev[0];
+        }
+        Hex::from_cube([q, r, s])
+    }
+
+    pub fn rotate_around(self, center: Hex, steps: isize) -> Hex {
+        center + (self - center).rotate(steps)
+    }
+}
+
+impl Add for Hex {
+    type Output = Hex;
+    fn add(self, other: Hex) -> Hex {
+        Hex::new(self.q + other.q, self.r + other.r)
+    }
+}
+
+impl AddAssign for Hex {
+    fn add_assign(&mut self, other: Hex) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Hex {
+    type Output = Hex;
+    fn sub(self, other: Hex) -> Hex {
+        Hex::new(self.q - other.q, self.r - other.r)
+    }
+}
+
+impl Neg for Hex {
+    type Output = Hex;
+    fn neg(self) -> Hex {
+        Hex::new(-self.q, -self.r)
+    }
+}
+
+impl Mul<isize> for Hex {
+    type Output = Hex;
+    fn mul(self, k: isize) -> Hex {
+        Hex::new(self.q * k, self.r * k)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directions() {
+        let dirs = parse_directions("esenee").unwrap();
+        assert_eq!(
+            dirs,
+            [Direction::E, Direction::SE, Direction::NE, Direction::E]
+        );
+        assert_eq!(Hex::ORIGIN.walk(dirs), Hex::new(3, 0));
+        assert_eq!(
+            Hex::ORIGIN.walk(parse_directions("nwwswee").unwrap()),
+            Hex::ORIGIN
+        );
+        assert!(parse_directions("nex").is_err());
+        assert!(parse_directions("ees").is_err());
+        for dir in Direction::ALL.iter() {
+            assert_eq!(dir.to_string().parse::<Direction>(), Ok(*dir));
+            assert_eq!(dir.offset() + dir.opposite().offset(), Hex::ORIGIN);
+            assert_eq!(dir.offset().rotate(1), dir.rotate(1).offset());
+        }
+    }
+
+    #[test]
+    fn test_geometry() {
+        let center = Hex::new(2, -1);
+        assert_eq!(center.neighbors().count(), 6);
+        assert!(center.neighbors().all(|n| n.distance(center) == 1));
+        assert_eq!(Hex::new(3, -3).distance(Hex::new(-2, 1)), 5);
+        for radius in 0..5 {
+            let ring = center.ring(radius);
+            assert_eq!(ring.len(), (6 * radius).max(1));
+            assert!(ring.iter().all(|h| h.distance(center) == radius));
+        }
+        assert_eq!(center.within(3).count(), 37);
+
+        let h = Hex::new(3, -1);
+        assert_eq!(h.rotate(6), h);
+        assert_eq!(h.rotate(-1).rotate(1), h);
+        assert_eq!(h.rotate(3), -h);
+        assert_eq!(Hex::new(1, 0).rotate(1), Hex::new(0, 1));
+        assert_eq!(
+            h.rotate_around(center, 2).distance(center),
+            h.distance(center)
+        );
+    }
+}
@@ -0,0 +1,325 @@
+//! Sets of integer intervals, and functions that shift intervals by an offset.
+//!
+//! Intervals are half-open (`start..end`), like `Range`. Use `insert_inclusive` for inputs
+//! that describe inclusive ranges (`start-end`).
+use std::cmp::{max, min};
+use std::ops::{Add, Range, RangeInclusive, Sub};
+
+/// Integer types that can be used as the bounds of an interval.
+pub trait Bound: Copy + Ord + Default + Add<Output = Self> + Sub<Output = Self> {
+    const ONE: Self;
+    fn to_i128(self) -> i128;
+    fn from_i128(value: i128) -> Self;
+}
+
+macro_rules! impl_bound {
+    ($($t:ty)*) => {
+        $(impl Bound for $t {
+            const ONE: Self = 1;
+            fn to_i128(self) -> i128 {
+                self as i128
+            }
+            fn from_i128(value: i128) -> Self {
+                Self::try_from(value).expect("value out of range")
+            }
+        })*
+    };
+}
+
+impl_bound!(i8 i16 i32 i64 i128 isize u8 u16 u32 u64 usize);
+
+/// A set of values, stored as a sorted list of disjoint and non-adjacent intervals.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct IntervalSet<T> {
+    spans: Vec<Range<T>>,
+}
+
+impl<T: Bound> IntervalSet<T> {
+    pub fn new() -> Self {
+        IntervalSet { spans: vec![] }
+    }
+
+    pub fn insert(&mut self, range: Range<T>) {
+        if range.is_empty() {
+            return;
+        }
+        // Touching intervals are merged as well
+        let lo = self.spans.partition_point(|s| s.end < range.start);
+        let hi = self.spans.partition_point(|s| s.start <= range.end);
+        let mut new = range;
+        if lo < hi {
+            new.start = min(new.start, self.spans[lo].start);
+            new.end = max(new.end, self.spans[hi - 1].end);
+        }
+        self.spans.splice(lo..hi, [new]);
+    }
+
+    pub fn insert_inclusive(&mut self, range: RangeInclusive<T>) {
+        self.insert(*range.start()..*range.end() + T::ONE);
+    }
+
+    pub fn remove(&mut self, range: Range<T>) {
+        if range.is_empty() {
+            return;
+        }
+        let lo = self.spans.partition_point(|s| s.end <= range.start);
+        let hi = self.spans.partition_point(|s| s.start < range.end);
+        if lo >= hi {
+            return;
+        }
+        let (first, last) = (self.spans[lo].start, self.spans[hi - 1].end);
+        let kept = [first..range.start, range.end..last]
+            .into_iter()
+            .filter(|r| !r.is_empty());
+        self.spans.splice(lo..hi, kept);
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        let idx = self.spans.partition_point(|s| s.end <= value);
+        idx < self.spans.len() && self.spans[idx].start <= value
+    }
+
+    /// Total number of values in the set
+    pub fn len(&self) -> T {
+        self.spans
+            .iter()
+            .fold(T::default(), |acc, s| acc + (s.end - s.start))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// The disjoint intervals in the set, in increasing order
+    pub fn spans(&self) -> &[Range<T>] {
+        &self.spans
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Range<T>> + '_ {
+        self.spans.iter().cloned()
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        let mut res = self.clone();
+        res.extend(other.iter());
+        res
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut spans = vec![];
+        let (mut i, mut j) = (0, 0);
+        while i < self.spans.len() && j < other.spans.len() {
+            let (a, b) = (&self.spans[i], &other.spans[j]);
+            let span = max(a.start, b.start)..min(a.end, b.end);
+            if !span.is_empty() {
+                spans.push(span);
+            }
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        IntervalSet { spans }
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut res = self.clone();
+        for span in other.iter() {
+            res.remove(span);
+        }
+        res
+    }
+
+    /// Values within `bounds` that are not in the set
+    pub fn complement(&self, bounds: Range<T>) -> Self {
+        IntervalSet::from(bounds).difference(self)
+    }
+}
+
+impl<T: Bound> From<Range<T>> for IntervalSet<T> {
+    fn from(range: Range<T>) -> Self {
+        let mut set = IntervalSet::new();
+        set.insert(range);
+        set
+    }
+}
+
+impl<T: Bound> Extend<Range<T>> for IntervalSet<T> {
+    fn extend<I: IntoIterator<Item = Range<T>>>(&mut self, iter: I) {
+        for range in iter {
+            self.insert(range);
+        }
+    }
+}
+
+impl<T: Bound> FromIterator<Range<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Range<T>>>(iter: I) -> Self {
+        let mut set = IntervalSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+/// A function that adds a different offset to each of a list of disjoint intervals, and leaves
+/// any other value unchanged.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct OffsetMap<T> {
+    pieces: Vec<(Range<T>, i128)>,
+}
+
+fn shift<T: Bound>(value: T, offset: i128) -> T {
+    T::from_i128(value.to_i128() + offset)
+}
+
+impl<T: Bound> OffsetMap<T> {
+    /// The identity function
+    pub fn new() -> Self {
+        OffsetMap { pieces: vec![] }
+    }
+
+    /// Shift the values in `range` by `offset`, overriding any previous offset for them
+    pub fn insert(&mut self, range: Range<T>, offset: i128) {
+        if range.is_empty() {
+            return;
+        }
+        let lo = self.pieces.partition_point(|(r, _)| r.end <= range.start);
+        let hi = self.pieces.partition_point(|(r, _)| r.start < range.end);
+        let mut replacement = vec![];
+        if lo < hi {
+            let (first, first_off) = self.pieces[lo].clone();
+            let (last, last_off) = self.pieces[hi - 1].clone();
+            replacement.push((first.start..range.start, first_off));
+            replacement.push((range.clone(), offset));
+            replacement.push((range.end..last.end, last_off));
+        } else {
+            replacement.push((range, offset));
+        }
+        replacement.retain(|(r, off)| !r.is_empty() && *off != 0);
+        self.pieces.splice(lo..hi, replacement);
+    }
+
+    /// The intervals that are shifted, with their offsets
+    pub fn pieces(&self) -> &[(Range<T>, i128)] {
+        &self.pieces
+    }
+
+    pub fn apply(&self, value: T) -> T {
+        let idx = self.pieces.partition_point(|(r, _)| r.end <= value);
+        match self.pieces.get(idx) {
+            Some((r, off)) if r.start <= value => shift(value, *off),
+            _ => value,
+        }
+    }
+
+    /// Split `range` into consecutive chunks that are shifted by the same offset
+    fn segments(&self, range: Range<T>) -> Vec<(Range<T>, i128)> {
+        let mut segments = vec![];
+        let mut cursor = range.start;
+        let idx = self.pieces.partition_point(|(r, _)| r.end <= range.start);
+        for (r, off) in self.pieces[idx..].iter() {
+            if r.start >= range.end {
+                break;
+            }
+            if r.start > cursor {
+                segments.push((cursor..r.start, 0));
+            }
+            let segment = max(r.start, cursor)..min(r.end, range.end);
+            cursor = segment.end;
+            segments.push((segment, *off));
+        }
+        if cursor < range.end {
+            segments.push((cursor..range.end, 0));
+        }
+        segments
+    }
+
+    /// The image of every value in `set`
+    pub fn apply_set(&self, set: &IntervalSet<T>) -> IntervalSet<T> {
+        set.iter()
+            .flat_map(|span| self.segments(span))
+            .map(|(r, off)| shift(r.start, off)..shift(r.end, off))
+            .collect()
+    }
+
+    /// A map equivalent to applying `self` and then `next`
+    pub fn compose(&self, next: &OffsetMap<T>) -> OffsetMap<T> {
+        let mut pieces = vec![];
+        for (r, off) in self.pieces.iter() {
+            let image = shift(r.start, *off)..shift(r.end, *off);
+            for (s, next_off) in next.segments(image) {
+                pieces.push((shift(s.start, -off)..shift(s.end, -off), off + next_off));
+            }
+        }
+        // Values that are not shifted by `self` are only shifted by `next`
+        for (r, next_off) in next.pieces.iter() {
+            for (s, off) in self.segments(r.clone()) {
+                if off == 0 {
+                    pieces.push((s, *next_off));
+                }
+            }
+        }
+        pieces.retain(|(_, off)| *off != 0);
+        pieces.sort_by_key(|(r, _)| r.start);
+
+        let mut merged: Vec<(Range<T>, i128)> = vec![];
+        for (r, off) in pieces {
+            match merged.last_mut() {
+                Some((last, last_off)) if last.end == r.start && *last_off == off => {
+                    last.end = r.end;
+                }
+                _ => merged.push((r, off)),
+            }
+        }
+        OffsetMap { pieces: merged }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set() {
+        let mut set: IntervalSet<i32> = [0..5, 10..15, 5..7].into_iter().collect();
+        assert_eq!(set.spans(), &[0..7, 10..15]);
+        set.insert_inclusive(16..=20);
+        assert_eq!(set.spans(), &[0..7, 10..15, 16..21]);
+        set.insert(14..16);
+        assert_eq!(set.spans(), &[0..7, 10..21]);
+        set.remove(3..12);
+        assert_eq!(set.spans(), &[0..3, 12..21]);
+        assert_eq!(set.len(), 12);
+        assert!(set.contains(0) && set.contains(20) && !set.contains(3) && !set.contains(21));
+
+        let other = IntervalSet::from(2..13);
+        assert_eq!(set.intersection(&other).spans(), &[2..3, 12..13]);
+        assert_eq!(set.union(&other), IntervalSet::from(0..21));
+        assert_eq!(set.difference(&other).spans(), &[0..2, 13..21]);
+        assert_eq!(set.complement(-5..25).spans(), &[-5..0, 3..12, 21..25]);
+    }
+
+    #[test]
+    fn test_offset_map() {
+        let mut first = OffsetMap::new();
+        first.insert(98..100usize, -48);
+        first.insert(50..98, 2);
+        assert_eq!(first.apply(79), 81);
+        assert_eq!(first.apply(10), 10);
+        assert_eq!(first.apply(99), 51);
+
+        let mut second = OffsetMap::new();
+        second.insert(15..52usize, -15);
+        second.insert(52..54, -15);
+        second.insert(0..15, 39);
+        let composed = first.compose(&second);
+        for x in 0..120 {
+            assert_eq!(composed.apply(x), second.apply(first.apply(x)), "{x}");
+        }
+
+        let seeds: IntervalSet<usize> = [79..93, 55..68].into_iter().collect();
+        let image = composed.apply_set(&seeds);
+        assert_eq!(image.len(), seeds.len());
+        assert!(seeds.iter().flatten().all(|x| image.contains(composed.apply(x))));
+    }
+}
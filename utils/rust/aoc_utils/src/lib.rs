@@ -8,6 +8,25 @@ use std::io;
 use std::path::PathBuf;
 pub extern crate clap;
 
+pub mod automaton;
+pub mod cuboid;
+pub mod grammar;
+pub mod graph;
+pub mod hex;
+pub mod intervals;
+pub mod linalg;
+pub mod matching;
+pub mod memo;
+pub mod miner;
+pub mod numtheory;
+pub mod partition;
+pub mod polygon;
+pub mod pratt;
+pub mod search;
+pub mod symbolic;
+pub mod tsp;
+pub mod unionfind;
+
 pub fn section<T>(timeit: bool, title: &str, code: impl FnOnce() -> T) -> T {
     print!("{} ", title);
     let now = std::time::Instant::now();
@@ -77,7 +96,7 @@ pub fn gcd(a: usize, b: usize) -> usize {
 }
 
 pub fn lcm(a: usize, b: usize) -> usize {
-    a * b / gcd(a, b)
+    numtheory::lcm(a, b).expect("lcm overflow")
 }
 
 #[macro_export]
@@ -122,6 +141,9 @@ macro_rules! solve_1 {
                 print!("{res:<15}");
                 res
             });
+            if let Some(stats) = $crate::search::take_stats() {
+                println!("\t\tSearch: {}", stats);
+            }
         }
         if parts.1 {
             $crate::section($timeit, "\tPart 2:", || {
@@ -129,6 +151,9 @@ macro_rules! solve_1 {
                 print!("{res:<15}");
                 res
             });
+            if let Some(stats) = $crate::search::take_stats() {
+                println!("\t\tSearch: {}", stats);
+            }
         }
     };
 }
@@ -137,7 +162,7 @@ macro_rules! solve_1 {
 macro_rules! aoc_main {
     ($($day:ident;)*) => {
 
-        $(mod $day;)*
+        $(pub mod $day;)*
 
         const PKG_NAME: &str = env!("CARGO_PKG_NAME");
 
@@ -0,0 +1,367 @@
+//! Exact linear algebra over the rationals.
+//!
+//! Numbers are fractions of arbitrary precision integers, so systems with huge coefficients
+//! (e.g. positions in the hundreds of trillions) are solved without rounding errors or
+//! overflows.
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{One, ToPrimitive, Zero};
+use std::fmt;
+use std::ops::{Index, IndexMut, Mul};
+
+pub type Rational = BigRational;
+
+pub fn rational(n: impl Into<BigInt>) -> Rational {
+    Rational::from_integer(n.into())
+}
+
+/// The values as integers, or `None` if any of them is not an integer or does not fit in an
+/// `i128`
+pub fn to_integers(values: &[Rational]) -> Option<Vec<i128>> {
+    values
+        .iter()
+        .map(|v| {
+            if v.is_integer() {
+                v.to_integer().to_i128()
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Solution {
+    None,
+    Unique(Vec<Rational>),
+    /// Every solution is `particular` plus a combination of the vectors in `null_space`
+    Many {
+        particular: Vec<Rational>,
+        null_space: Vec<Vec<Rational>>,
+    },
+}
+
+impl Solution {
+    pub fn unique(self) -> Option<Vec<Rational>> {
+        match self {
+            Solution::Unique(x) => Some(x),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Matrix {
+    rows: usize,
+    cols: usize,
+    data: Vec<Rational>,
+}
+
+impl Matrix {
+    pub fn zeros(rows: usize, cols: usize) -> Self {
+        Matrix {
+            rows,
+            cols,
+            data: vec![Rational::zero(); rows * cols],
+        }
+    }
+
+    pub fn identity(n: usize) -> Self {
+        let mut m = Matrix::zeros(n, n);
+        for i in 0..n {
+            m[(i, i)] = Rational::one();
+        }
+        m
+    }
+
+    /// Matrix with the given rows, of integers (or anything that converts to a `BigInt`)
+    pub fn from_rows<T: Into<BigInt>>(
+        rows: impl IntoIterator<Item = impl IntoIterator<Item = T>>,
+    ) -> Self {
+        let rows: Vec<Vec<Rational>> = rows
+            .into_iter()
+            .map(|row| row.into_iter().map(rational).collect())
+            .collect();
+        Matrix::from_rational_rows(rows)
+    }
+
+    pub fn from_rational_rows(rows: Vec<Vec<Rational>>) -> Self {
+        let cols = rows.first().map_or(0, |row| row.len());
+        assert!(
+            rows.iter().all(|row| row.len() == cols),
+            "rows of different lengths"
+        );
+        Matrix {
+            rows: rows.len(),
+            cols,
+            data: rows.into_iter().flatten().collect(),
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn row(&self, r: usize) -> &[Rational] {
+        &self.data[r * self.cols..(r + 1) * self.cols]
+    }
+
+    pub fn transpose(&self) -> Matrix {
+        let mut t = Matrix::zeros(self.cols, self.rows);
+        for r in 0..self.rows {
+            for c in 0..self.cols {
+                t[(c, r)] = self[(r, c)].clone();
+            }
+        }
+        t
+    }
+
+    pub fn mul_vec(&self, v: &[Rational]) -> Vec<Rational> {
+        assert_eq!(v.len(), self.cols, "wrong vector length");
+        (0..self.rows)
+            .map(|r| self.row(r).iter().zip(v).map(|(a, b)| a * b).sum())
+            .collect()
+    }
+
+    fn swap_rows(&mut self, a: usize, b: usize) {
+        for c in 0..self.cols {
+            self.data.swap(a * self.cols + c, b * self.cols + c);
+        }
+    }
+
+    /// Reduced row echelon form (Gauss-Jordan elimination), and the column of each pivot
+    pub fn rref(&self) -> (Matrix, Vec<usize>) {
+        let mut m = self.clone();
+        let mut pivots = vec![];
+        for c in 0..m.cols {
+            let r = pivots.len();
+            let Some(p) = (r..m.rows).find(|&p| !m[(p, c)].is_zero()) else {
+                continue;
+            };
+            m.swap_rows(r, p);
+            let pivot = m[(r, c)].clone();
+            for k in c..m.cols {
+                m[(r, k)] = &m[(r, k)] / &pivot;
+            }
+            for other in 0..m.rows {
+                let factor = m[(other, c)].clone();
+                if other == r || factor.is_zero() {
+                    continue;
+                }
+                for k in c..m.cols {
+                    let delta = &factor * &m[(r, k)];
+                    m[(other, k)] -= delta;
+                }
+            }
+            pivots.push(c);
+            if pivots.len() == m.rows {
+                break;
+            }
+        }
+        (m, pivots)
+    }
+
+    pub fn rank(&self) -> usize {
+        self.rref().1.len()
+    }
+
+    /// Determinant, or `None` if the matrix is not square
+    pub fn determinant(&self) -> Option<Rational> {
+        if self.rows != self.cols {
+            return None;
+        }
+        let mut m = self.clone();
+        let mut det = Rational::one();
+        for c in 0..m.cols {
+            let Some(p) = (c..m.rows).find(|&p| !m[(p, c)].is_zero()) else {
+                return Some(Rational::zero());
+            };
+            if p != c {
+                m.swap_rows(c, p);
+                det = -det;
+            }
+            let pivot = m[(c, c)].clone();
+            det *= &pivot;
+            for r in c + 1..m.rows {
+                let factor = &m[(r, c)] / &pivot;
+                for k in c..m.cols {
+                    let delta = &factor * &m[(c, k)];
+                    m[(r, k)] -= delta;
+                }
+            }
+        }
+        Some(det)
+    }
+
+    /// Inverse, if the matrix is square and not singular
+    pub fn inverse(&self) -> Option<Matrix> {
+        if self.rows != self.cols {
+            return None;
+        }
+        let n = self.rows;
+        let mut augmented = Matrix::zeros(n, 2 * n);
+        for r in 0..n {
+            for c in 0..n {
+                augmented[(r, c)] = self[(r, c)].clone();
+            }
+            augmented[(r, n + r)] = Rational::one();
+        }
+        let (reduced, pivots) = augmented.rref();
+        if pivots.len() < n || pivots[n - 1] >= n {
+            return None;
+        }
+        let mut inverse = Matrix::zeros(n, n);
+        for r in 0..n {
+            for c in 0..n {
+                inverse[(r, c)] = reduced[(r, n + c)].clone();
+            }
+        }
+        Some(inverse)
+    }
+
+    /// A basis of the vectors `x` such that `self * x == 0`
+    pub fn null_space(&self) -> Vec<Vec<Rational>> {
+        let (reduced, pivots) = self.rref();
+        (0..self.cols)
+            .filter(|c| !pivots.contains(c))
+            .map(|free| {
+                let mut v = vec![Rational::zero(); self.cols];
+                v[free] = Rational::one();
+                for (r, &p) in pivots.iter().enumerate() {
+                    v[p] = -reduced[(r, free)].clone();
+                }
+                v
+            })
+            .collect()
+    }
+
+    /// Solve `self * x == b`
+    pub fn solve(&self, b: &[Rational]) -> Solution {
+        assert_eq!(b.len(), self.rows, "wrong vector length");
+        let mut augmented = Matrix::zeros(self.rows, self.cols + 1);
+        for r in 0..self.rows {
+            for c in 0..self.cols {
+                augmented[(r, c)] = self[(r, c)].clone();
+            }
+            augmented[(r, self.cols)] = b[r].clone();
+        }
+        let (reduced, pivots) = augmented.rref();
+        if pivots.last() == Some(&self.cols) {
+            return Solution::None;
+        }
+        let mut particular = vec![Rational::zero(); self.cols];
+        for (r, &p) in pivots.iter().enumerate() {
+            particular[p] = reduced[(r, self.cols)].clone();
+        }
+        if pivots.len() == self.cols {
+            Solution::Unique(particular)
+        } else {
+            Solution::Many {
+                particular,
+                null_space: self.null_space(),
+            }
+        }
+    }
+}
+
+impl Index<(usize, usize)> for Matrix {
+    type Output = Rational;
+    fn index(&self, (r, c): (usize, usize)) -> &Rational {
+        assert!(r < self.rows && c < self.cols, "index out of bounds");
+        &self.data[r * self.cols + c]
+    }
+}
+
+impl IndexMut<(usize, usize)> for Matrix {
+    fn index_mut(&mut self, (r, c): (usize, usize)) -> &mut Rational {
+        assert!(r < self.rows && c < self.cols, "index out of bounds");
+        &mut self.data[r * self.cols + c]
+    }
+}
+
+impl Mul for &Matrix {
+    type Output = Matrix;
+    fn mul(self, other: &Matrix) -> Matrix {
+        assert_eq!(self.cols, other.rows, "incompatible dimensions");
+        let mut m = Matrix::zeros(self.rows, other.cols);
+        for r in 0..self.rows {
+            for c in 0..other.cols {
+                m[(r, c)] = (0..self.cols).map(|k| &self[(r, k)] * &other[(k, c)]).sum();
+            }
+        }
+        m
+    }
+}
+
+impl fmt::Display for Matrix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for r in 0..self.rows {
+            let row: Vec<String> = self.row(r).iter().map(|v| v.to_string()).collect();
+            writeln!(f, "[{}]", row.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ints(values: &[i64]) -> Vec<Rational> {
+        values.iter().map(|&v| rational(v)).collect()
+    }
+
+    #[test]
+    fn test_solve() {
+        let m = Matrix::from_rows([[2, 1, -1], [-3, -1, 2], [-2, 1, 2]]);
+        assert_eq!(m.rank(), 3);
+        assert_eq!(m.determinant(), Some(rational(-1)));
+        let x = m.solve(&ints(&[8, -11, -3])).unique().unwrap();
+        assert_eq!(to_integers(&x), Some(vec![2, 3, -1]));
+        let inverse = m.inverse().unwrap();
+        assert_eq!(&m * &inverse, Matrix::identity(3));
+        assert_eq!(inverse.mul_vec(&ints(&[8, -11, -3])), x);
+
+        // Huge coefficients do not lose precision
+        let big = 400_000_000_000_000i64;
+        let m = Matrix::from_rows([[big, 3], [big + 1, 5]]);
+        let x = m
+            .solve(&ints(&[3 * big + 21, 3 * big + 38]))
+            .unique()
+            .unwrap();
+        assert_eq!(to_integers(&x), Some(vec![3, 7]));
+        let half = m.solve(&ints(&[1, 0])).unique().unwrap();
+        assert_eq!(to_integers(&half), None);
+    }
+
+    #[test]
+    fn test_singular() {
+        let m = Matrix::from_rows([[1, 2, 3], [2, 4, 6], [1, 0, 1]]);
+        assert_eq!(m.rank(), 2);
+        assert_eq!(m.determinant(), Some(rational(0)));
+        assert_eq!(m.inverse(), None);
+        let null = m.null_space();
+        assert_eq!(null, [ints(&[-1, -1, 1])]);
+        assert!(m.mul_vec(&null[0]).iter().all(|v| v.is_zero()));
+
+        assert_eq!(m.solve(&ints(&[1, 1, 0])), Solution::None);
+        match m.solve(&ints(&[4, 8, 2])) {
+            Solution::Many {
+                particular,
+                null_space,
+            } => {
+                assert_eq!(m.mul_vec(&particular), ints(&[4, 8, 2]));
+                assert_eq!(null_space, null);
+            }
+            other => panic!("expected many solutions, got {:?}", other),
+        }
+        assert_eq!(
+            Matrix::from_rows([[1, 2]]).transpose(),
+            Matrix::from_rows([[1], [2]])
+        );
+    }
+}
@@ -0,0 +1,258 @@
+//! Assignment puzzles: pair every item on the left (e.g. ticket fields) with a different item
+//! on the right (e.g. columns), given the candidates of each one.
+//!
+//! Instead of repeatedly fixing the items that only have one candidate left (which gets stuck
+//! when there is no such item), `Bipartite::assign` finds a maximum matching with
+//! Hopcroft-Karp, and then every candidate that appears in at least one maximum matching. That
+//! tells whether the solution is unique, and what the alternatives are when it is not.
+use std::collections::VecDeque;
+
+/// Bipartite graph between the items `0..left` and `0..right`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Bipartite {
+    candidates: Vec<Vec<usize>>,
+    right: usize,
+}
+
+/// State of a run of Hopcroft-Karp, optionally ignoring one item on each side
+struct HopcroftKarp<'a> {
+    candidates: &'a [Vec<usize>],
+    skip: (Option<usize>, Option<usize>),
+    left: Vec<Option<usize>>,
+    right: Vec<Option<usize>>,
+    /// Layer of each item on the left in the current phase
+    dist: Vec<usize>,
+}
+
+impl HopcroftKarp<'_> {
+    fn edges(&self, l: usize) -> impl Iterator<Item = usize> + '_ {
+        let skip = self.skip.1;
+        self.candidates[l]
+            .iter()
+            .copied()
+            .filter(move |&r| Some(r) != skip)
+    }
+
+    fn free(&self) -> Vec<usize> {
+        (0..self.left.len())
+            .filter(|&l| self.left[l].is_none() && Some(l) != self.skip.0)
+            .collect()
+    }
+
+    /// Assign layers with a BFS from the free items. Returns false if there are no augmenting
+    /// paths.
+    fn layers(&mut self) -> bool {
+        let mut queue: VecDeque<usize> = VecDeque::new();
+        self.dist = vec![usize::MAX; self.left.len()];
+        for l in self.free() {
+            self.dist[l] = 0;
+            queue.push_back(l);
+        }
+        let mut found = false;
+        while let Some(l) = queue.pop_front() {
+            let edges: Vec<usize> = self.edges(l).collect();
+            for r in edges {
+                match self.right[r] {
+                    None => found = true,
+                    Some(next) if self.dist[next] == usize::MAX => {
+                        self.dist[next] = self.dist[l] + 1;
+                        queue.push_back(next);
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+        found
+    }
+
+    /// Find an augmenting path from `l` that follows the layers
+    fn augment(&mut self, l: usize) -> bool {
+        let edges: Vec<usize> = self.edges(l).collect();
+        for r in edges {
+            let free = match self.right[r] {
+                None => true,
+                Some(next) => self.dist[next] == self.dist[l] + 1 && self.augment(next),
+            };
+            if free {
+                self.left[l] = Some(r);
+                self.right[r] = Some(l);
+                return true;
+            }
+        }
+        self.dist[l] = usize::MAX;
+        false
+    }
+
+    fn run(mut self) -> Vec<Option<usize>> {
+        while self.layers() {
+            for l in self.free() {
+                self.augment(l);
+            }
+        }
+        self.left
+    }
+}
+
+impl Bipartite {
+    pub fn new(left: usize, right: usize) -> Self {
+        Bipartite {
+            candidates: vec![vec![]; left],
+            right,
+        }
+    }
+
+    /// Graph where item `l` on the left can be paired with any item in `candidates[l]`
+    pub fn from_candidates(candidates: Vec<Vec<usize>>, right: usize) -> Self {
+        let mut graph = Bipartite::new(candidates.len(), right);
+        for (l, options) in candidates.into_iter().enumerate() {
+            for r in options {
+                graph.add_edge(l, r);
+            }
+        }
+        graph
+    }
+
+    pub fn add_edge(&mut self, l: usize, r: usize) {
+        assert!(r < self.right, "item {} is not on the right side", r);
+        if !self.candidates[l].contains(&r) {
+            self.candidates[l].push(r);
+            self.candidates[l].sort_unstable();
+        }
+    }
+
+    pub fn candidates(&self, l: usize) -> &[usize] {
+        &self.candidates[l]
+    }
+
+    fn matching_without(&self, skip: (Option<usize>, Option<usize>)) -> Vec<Option<usize>> {
+        HopcroftKarp {
+            candidates: &self.candidates,
+            skip,
+            left: vec![None; self.candidates.len()],
+            right: vec![None; self.right],
+            dist: vec![],
+        }
+        .run()
+    }
+
+    /// A matching with as many pairs as possible: the partner of every item on the left
+    pub fn max_matching(&self) -> Vec<Option<usize>> {
+        self.matching_without((None, None))
+    }
+
+    /// A maximum matching, and every pair that is part of some maximum matching
+    pub fn assign(&self) -> Assignment {
+        let matching = self.max_matching();
+        let size = matching.iter().flatten().count();
+        let options = (0..self.candidates.len())
+            .map(|l| {
+                self.candidates[l]
+                    .iter()
+                    .copied()
+                    .filter(|&r| {
+                        // Pairing l and r still leaves a maximum matching for everything else
+                        matching[l] == Some(r)
+                            || self
+                                .matching_without((Some(l), Some(r)))
+                                .iter()
+                                .flatten()
+                                .count()
+                                == size - 1
+                    })
+                    .collect()
+            })
+            .collect();
+        Assignment { matching, options }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assignment {
+    /// Partner of every item on the left, in one maximum matching
+    pub matching: Vec<Option<usize>>,
+    /// Candidates of every item on the left that are part of some maximum matching
+    pub options: Vec<Vec<usize>>,
+}
+
+impl Assignment {
+    /// Number of pairs in a maximum matching
+    pub fn size(&self) -> usize {
+        self.matching.iter().flatten().count()
+    }
+
+    /// Whether every item on the left has a partner
+    pub fn is_complete(&self) -> bool {
+        self.matching.iter().all(Option::is_some)
+    }
+
+    /// Whether there is only one maximum matching
+    pub fn is_unique(&self) -> bool {
+        // Otherwise some items could take turns to get the same partner
+        self.options.iter().all(|options| options.len() <= 1)
+            && self.options.iter().filter(|o| !o.is_empty()).count() == self.size()
+    }
+
+    /// Items on the left with more than one possible partner, and their options
+    pub fn ambiguities(&self) -> Vec<(usize, &[usize])> {
+        self.options
+            .iter()
+            .enumerate()
+            .filter(|(_, options)| options.len() > 1)
+            .map(|(l, options)| (l, options.as_slice()))
+            .collect()
+    }
+
+    /// The partner of every item on the left, if there is exactly one way to pair all of them
+    pub fn solution(&self) -> Result<Vec<usize>, String> {
+        if !self.is_complete() {
+            let unmatched: Vec<usize> = (0..self.matching.len())
+                .filter(|&l| self.matching[l].is_none())
+                .collect();
+            return Err(format!("no partner for items {:?}", unmatched));
+        }
+        if !self.is_unique() {
+            return Err(format!("ambiguous assignment: {:?}", self.ambiguities()));
+        }
+        Ok(self.matching.iter().flatten().copied().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assignment() {
+        let graph =
+            Bipartite::from_candidates(vec![vec![0, 1], vec![1, 2], vec![0, 1, 2], vec![2, 3]], 4);
+        assert_eq!(graph.max_matching().iter().flatten().count(), 4);
+        let assignment = graph.assign();
+        assert!(assignment.is_complete() && !assignment.is_unique());
+        // The last item can only get 3, but there are several ways to pair the rest
+        assert_eq!(
+            assignment.options,
+            [vec![0, 1], vec![1, 2], vec![0, 1, 2], vec![3]]
+        );
+
+        let graph =
+            Bipartite::from_candidates(vec![vec![0, 1], vec![1, 2], vec![0, 2], vec![2, 3]], 4);
+        let assignment = graph.assign();
+        assert_eq!(assignment.ambiguities().len(), 3);
+        assert!(assignment.solution().is_err());
+
+        // Fixing the items with a single candidate one at a time would also work here
+        let graph =
+            Bipartite::from_candidates(vec![vec![0, 1, 3], vec![1, 2, 3], vec![0, 1], vec![0]], 4);
+        assert_eq!(graph.assign().options, [vec![3], vec![2], vec![1], vec![0]]);
+        assert_eq!(graph.assign().solution(), Ok(vec![3, 2, 1, 0]));
+
+        // Two items competing for the same partner
+        let graph = Bipartite::from_candidates(vec![vec![0], vec![0], vec![1]], 2);
+        let assignment = graph.assign();
+        assert_eq!(assignment.size(), 2);
+        assert!(!assignment.is_complete() && !assignment.is_unique());
+        assert_eq!(assignment.ambiguities(), []);
+        assert_eq!(assignment.options, [vec![0], vec![0], vec![1]]);
+        assert!(assignment.solution().is_err());
+    }
+}
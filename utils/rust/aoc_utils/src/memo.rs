@@ -0,0 +1,131 @@
+//! Cache for recursive functions.
+//!
+//! The function receives the `Memo` and passes it along to its recursive calls:
+//!
+//! ```
+//! use aoc_utils::memo::Memo;
+//!
+//! fn fib(n: u64, memo: &mut Memo<u64, u64>) -> u64 {
+//!     memo.get(n, |memo| if n < 2 { n } else { fib(n - 1, memo) + fib(n - 2, memo) })
+//! }
+//!
+//! let mut memo = Memo::new();
+//! assert_eq!(fib(90, &mut memo), 2880067194370816120);
+//! ```
+//!
+//! Keys can borrow from the input (e.g. `&str` or `&[T]`), as long as the input outlives the
+//! `Memo`. A `Memo` can be reused for different inputs with `clear`.
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+#[derive(Debug, Clone)]
+pub struct Memo<K, V> {
+    cache: HashMap<K, V>,
+    hits: usize,
+}
+
+impl<K, V> Default for Memo<K, V> {
+    fn default() -> Self {
+        Memo {
+            cache: HashMap::new(),
+            hits: 0,
+        }
+    }
+}
+
+impl<K: Hash + Eq, V: Clone> Memo<K, V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The cached value for `key`, or the result of `compute` (which is then cached)
+    pub fn get(&mut self, key: K, compute: impl FnOnce(&mut Self) -> V) -> V {
+        if let Some(value) = self.cache.get(&key) {
+            self.hits += 1;
+            return value.clone();
+        }
+        let value = compute(self);
+        self.cache.insert(key, value.clone());
+        value
+    }
+
+    /// Like `get`, but only converts the key to its owned form if it is not cached, e.g. to
+    /// look up a `&str` in a `Memo<String, _>`.
+    pub fn get_borrowed<Q>(&mut self, key: &Q, compute: impl FnOnce(&mut Self) -> V) -> V
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned<Owned = K> + ?Sized,
+    {
+        if let Some(value) = self.cache.get(key) {
+            self.hits += 1;
+            return value.clone();
+        }
+        let value = compute(self);
+        self.cache.insert(key.to_owned(), value.clone());
+        value
+    }
+
+    pub fn clear(&mut self) {
+        self.cache.clear();
+        self.hits = 0;
+    }
+
+    /// Number of cached values
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+
+    /// Number of times a cached value was reused
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count_paths(x: usize, y: usize, memo: &mut Memo<(usize, usize), u64>) -> u64 {
+        memo.get((x, y), |memo| {
+            if x == 0 || y == 0 {
+                1
+            } else {
+                count_paths(x - 1, y, memo) + count_paths(x, y - 1, memo)
+            }
+        })
+    }
+
+    fn splits(word: &str, memo: &mut Memo<String, usize>) -> usize {
+        memo.get_borrowed(word, |memo| {
+            if word.is_empty() {
+                return 1;
+            }
+            ["a", "ab", "b"]
+                .iter()
+                .filter_map(|p| word.strip_prefix(p))
+                .map(|rest| splits(rest, memo))
+                .sum()
+        })
+    }
+
+    #[test]
+    fn test_memo() {
+        let mut memo = Memo::new();
+        assert_eq!(count_paths(16, 16, &mut memo), 601080390);
+        // Every point but (0, 0), which is never reached
+        assert_eq!(memo.len(), 17 * 17 - 1);
+        assert!(memo.hits() > 0);
+        memo.clear();
+        assert!(memo.is_empty());
+        assert_eq!(count_paths(2, 2, &mut memo), 6);
+
+        let mut memo = Memo::new();
+        assert_eq!(splits("abab", &mut memo), 4);
+        assert_eq!(memo.len(), 5);
+    }
+}
@@ -0,0 +1,245 @@
+//! MD5 of a salt followed by a counter (`abc0`, `abc1`, ...), for the puzzles that look for
+//! hashes with some property.
+//!
+//! The counters are hashed in batches, with every batch split across threads, and the matches
+//! are always returned in counter order. Hashes can also be stretched (hashing the hex digest
+//! again, some number of times), and `Hashes` keeps them for puzzles that look ahead.
+use std::collections::VecDeque;
+use std::io::Write;
+use std::thread;
+
+pub type Digest = [u8; 16];
+
+/// Hex digit `i` of the digest
+pub fn nibble(digest: &Digest, i: usize) -> u8 {
+    if i.is_multiple_of(2) {
+        digest[i / 2] >> 4
+    } else {
+        digest[i / 2] & 0xf
+    }
+}
+
+/// Number of `0` at the start of the hex digest
+pub fn leading_zeros(digest: &Digest) -> usize {
+    (0..32).take_while(|&i| nibble(digest, i) == 0).count()
+}
+
+/// Lowercase hex digest
+pub fn hex(digest: &Digest) -> String {
+    String::from_utf8(hex_bytes(digest).to_vec()).unwrap()
+}
+
+fn hex_bytes(digest: &Digest) -> [u8; 32] {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut out = [0; 32];
+    for i in 0..32 {
+        out[i] = DIGITS[nibble(digest, i) as usize];
+    }
+    out
+}
+
+#[derive(Clone)]
+pub struct Miner {
+    salt: md5::Context,
+    stretch: usize,
+    threads: usize,
+    batch: usize,
+}
+
+impl Miner {
+    /// Miner that uses every available core
+    pub fn new(salt: &str) -> Self {
+        let mut context = md5::Context::new();
+        context.consume(salt.as_bytes());
+        Miner {
+            salt: context,
+            stretch: 0,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            batch: 1 << 12,
+        }
+    }
+
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// How many counters every thread hashes at a time
+    pub fn batch(mut self, batch: usize) -> Self {
+        self.batch = batch.max(1);
+        self
+    }
+
+    /// Hash the hex digest again `rounds` times
+    pub fn stretch(mut self, rounds: usize) -> Self {
+        self.stretch = rounds;
+        self
+    }
+
+    pub fn hash(&self, counter: usize) -> Digest {
+        let mut context = self.salt.clone();
+        let mut buf = [0u8; 20];
+        let mut cursor = &mut buf[..];
+        write!(cursor, "{}", counter).unwrap();
+        let len = 20 - cursor.len();
+        context.consume(&buf[..len]);
+        let mut digest = context.compute().0;
+        for _ in 0..self.stretch {
+            digest = md5::compute(hex_bytes(&digest)).0;
+        }
+        digest
+    }
+
+    /// Hashes of the counters in `start..start + len`, computed in parallel
+    fn hash_range(&self, start: usize, len: usize) -> Vec<Digest> {
+        let chunk = len.div_ceil(self.threads);
+        thread::scope(|scope| {
+            let handles: Vec<_> = (0..self.threads)
+                .map(|t| {
+                    let from = start + (t * chunk).min(len);
+                    let to = start + ((t + 1) * chunk).min(len);
+                    scope.spawn(move || (from..to).map(|i| self.hash(i)).collect::<Vec<_>>())
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().unwrap())
+                .collect()
+        })
+    }
+
+    /// Counters from `start` whose hash passes `test`, with their hash, in order
+    pub fn matches<F>(&self, start: usize, test: F) -> Matches<'_, F>
+    where
+        F: Fn(&Digest) -> bool,
+    {
+        Matches {
+            miner: self,
+            test,
+            next: start,
+            found: VecDeque::new(),
+        }
+    }
+
+    /// First counter from `start` whose hash passes `test`
+    pub fn find(&self, start: usize, test: impl Fn(&Digest) -> bool) -> Option<(usize, Digest)> {
+        self.matches(start, test).next()
+    }
+
+    /// First `n` counters whose hash passes `test`
+    pub fn first_n(&self, n: usize, test: impl Fn(&Digest) -> bool) -> Vec<(usize, Digest)> {
+        self.matches(0, test).take(n).collect()
+    }
+
+    /// First match for each of `positions` slots, where `slot` tells which slot a hash fills
+    /// (or `None` if it is not a match). Slots out of range are ignored.
+    pub fn first_at_each(
+        &self,
+        positions: usize,
+        slot: impl Fn(&Digest) -> Option<usize>,
+    ) -> Vec<(usize, Digest)> {
+        let mut found = vec![None; positions];
+        let mut missing = positions;
+        let fits = |d: &Digest| slot(d).is_some_and(|s| s < positions);
+        if missing > 0 {
+            for (counter, digest) in self.matches(0, fits) {
+                let s = slot(&digest).unwrap();
+                if found[s].is_none() {
+                    found[s] = Some((counter, digest));
+                    missing -= 1;
+                    if missing == 0 {
+                        break;
+                    }
+                }
+            }
+        }
+        found.into_iter().flatten().collect()
+    }
+}
+
+pub struct Matches<'a, F> {
+    miner: &'a Miner,
+    test: F,
+    next: usize,
+    found: VecDeque<(usize, Digest)>,
+}
+
+impl<F: Fn(&Digest) -> bool> Iterator for Matches<'_, F> {
+    type Item = (usize, Digest);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.found.is_empty() {
+            let len = self.miner.batch * self.miner.threads;
+            let hashes = self.miner.hash_range(self.next, len);
+            let start = self.next;
+            self.found.extend(
+                hashes
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(_, d)| (self.test)(d))
+                    .map(|(i, d)| (start + i, d)),
+            );
+            self.next += len;
+        }
+        self.found.pop_front()
+    }
+}
+
+/// Hashes computed so far, for puzzles that check the hashes that follow a candidate
+#[derive(Clone)]
+pub struct Hashes {
+    miner: Miner,
+    cache: Vec<Digest>,
+}
+
+impl Hashes {
+    pub fn new(miner: Miner) -> Self {
+        Hashes {
+            miner,
+            cache: vec![],
+        }
+    }
+
+    /// Hash of a counter, computing the next batch in parallel if needed
+    pub fn get(&mut self, counter: usize) -> Digest {
+        while self.cache.len() <= counter {
+            let len = self.miner.batch * self.miner.threads;
+            let more = self.miner.hash_range(self.cache.len(), len);
+            self.cache.extend(more);
+        }
+        self.cache[counter]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_miner() {
+        let miner = Miner::new("abc").threads(3).batch(1000);
+        assert_eq!(hex(&miner.hash(0)), "577571be4de9dcce85a041ba0410f29f");
+        assert_eq!(leading_zeros(&miner.hash(3231929)), 5);
+
+        let first = miner.first_n(3, |d| leading_zeros(d) >= 5);
+        let counters: Vec<usize> = first.iter().map(|m| m.0).collect();
+        assert_eq!(counters, [3231929, 5017308, 5278568]);
+        assert_eq!(
+            miner.find(3231930, |d| leading_zeros(d) >= 5),
+            Some(first[1])
+        );
+
+        let password: String = miner
+            .first_at_each(8, |d| {
+                (leading_zeros(d) >= 5).then(|| nibble(d, 5) as usize)
+            })
+            .iter()
+            .map(|(_, d)| char::from(hex_bytes(d)[6]))
+            .collect();
+        assert_eq!(password, "05ace8e3");
+
+        let mut stretched = Hashes::new(Miner::new("abc").stretch(2016).batch(8));
+        assert_eq!(hex(&stretched.get(0)), "a107ff634856bb300138cac6568c0f24");
+        assert_eq!(stretched.get(10), Miner::new("abc").stretch(2016).hash(10));
+    }
+}
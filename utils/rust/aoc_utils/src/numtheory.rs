@@ -0,0 +1,226 @@
+//! Number theory helpers: extended gcd, modular inverse and exponentiation,
+//! the chinese remainder theorem and discrete logarithms.
+//!
+//! Every function works with any primitive integer type. The computations are done with
+//! `i128` internally, and `None` is returned if a value does not fit in the original type
+//! (or in an `i128`) instead of overflowing.
+use std::collections::HashMap;
+
+/// Any primitive integer type.
+pub trait Int: Copy + TryFrom<i128> + TryInto<i128> {}
+
+impl<T: Copy + TryFrom<i128> + TryInto<i128>> Int for T {}
+
+fn wide<T: Int>(x: T) -> Option<i128> {
+    x.try_into().ok()
+}
+
+fn narrow<T: Int>(x: i128) -> Option<T> {
+    T::try_from(x).ok()
+}
+
+/// Returns `(g, x, y)` such that `a * x + b * y == g == gcd(a, b)`.
+pub fn ext_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_s, mut s) = (1, 0);
+    let (mut old_t, mut t) = (0, 1);
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_s, s) = (s, old_s - q * s);
+        (old_t, t) = (t, old_t - q * t);
+    }
+    if old_r < 0 {
+        (-old_r, -old_s, -old_t)
+    } else {
+        (old_r, old_s, old_t)
+    }
+}
+
+pub fn gcd<T: Int>(a: T, b: T) -> Option<T> {
+    narrow(ext_gcd(wide(a)?, wide(b)?).0)
+}
+
+/// Least common multiple, or `None` if it does not fit in `T`.
+pub fn lcm<T: Int>(a: T, b: T) -> Option<T> {
+    let (a, b) = (wide(a)?, wide(b)?);
+    if a == 0 || b == 0 {
+        return narrow(0);
+    }
+    let g = ext_gcd(a, b).0;
+    narrow((a / g).checked_mul(b)?.abs())
+}
+
+/// `a * b mod m`, for `a` and `b` in `0..m`, without overflowing.
+fn mul_mod(a: i128, b: i128, m: i128) -> i128 {
+    if let Some(p) = a.checked_mul(b) {
+        return p % m;
+    }
+    // Double and add, which only needs m < 2^126
+    let (mut a, mut b, mut res) = (a, b, 0);
+    while b > 0 {
+        if b & 1 == 1 {
+            res = (res + a) % m;
+        }
+        a = (a << 1) % m;
+        b >>= 1;
+    }
+    res
+}
+
+fn pow_mod(base: i128, mut exp: i128, m: i128) -> i128 {
+    let mut base = base.rem_euclid(m);
+    let mut res = 1 % m;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            res = mul_mod(res, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exp >>= 1;
+    }
+    res
+}
+
+/// `base ^ exp mod m`. Returns `None` for non-positive moduli or negative exponents.
+pub fn mod_pow<T: Int>(base: T, exp: T, m: T) -> Option<T> {
+    let (base, exp, m) = (wide(base)?, wide(exp)?, wide(m)?);
+    if m <= 0 || exp < 0 {
+        return None;
+    }
+    narrow(pow_mod(base, exp, m))
+}
+
+/// The `x` in `0..m` such that `a * x == 1 mod m`, if `a` and `m` are coprime.
+pub fn mod_inv<T: Int>(a: T, m: T) -> Option<T> {
+    let (a, m) = (wide(a)?, wide(m)?);
+    if m <= 0 {
+        return None;
+    }
+    let (g, x, _) = ext_gcd(a.rem_euclid(m), m);
+    if g != 1 {
+        return None;
+    }
+    narrow(x.rem_euclid(m))
+}
+
+/// Solve a system of congruences `x == r_i mod m_i`, where the moduli need not be coprime.
+///
+/// Returns `(x, m)`, where `m` is the lcm of all the moduli and `x` the smallest non-negative
+/// solution, so every solution is `x + k * m`. Returns `None` if the congruences are
+/// incompatible or the result does not fit in `T`.
+pub fn crt<T: Int>(congruences: &[(T, T)]) -> Option<(T, T)> {
+    let (mut rem, mut modulo) = (0i128, 1i128);
+    for &(r, m) in congruences {
+        let (r, m) = (wide(r)?, wide(m)?);
+        if m <= 0 {
+            return None;
+        }
+        let r = r.rem_euclid(m);
+        let (g, inv, _) = ext_gcd(modulo, m);
+        if (r - rem) % g != 0 {
+            return None;
+        }
+        let step = m / g;
+        let lcm = modulo.checked_mul(step)?;
+        let k = mul_mod(
+            ((r - rem) / g).rem_euclid(step),
+            inv.rem_euclid(step),
+            step,
+        );
+        rem = (rem + mul_mod(modulo, k, lcm)) % lcm;
+        modulo = lcm;
+    }
+    Some((narrow(rem)?, narrow(modulo)?))
+}
+
+/// Smallest `x >= 0` such that `base ^ x == target mod m`, using baby-step giant-step.
+/// `base` and `m` do not need to be coprime.
+pub fn discrete_log<T: Int>(base: T, target: T, m: T) -> Option<T> {
+    let (base, target, mut m) = (wide(base)?, wide(target)?, wide(m)?);
+    if m <= 0 {
+        return None;
+    }
+    let base = base.rem_euclid(m);
+    let mut target = target.rem_euclid(m);
+    if m == 1 {
+        return narrow(0);
+    }
+
+    // Divide out the common factors of base and m, so the rest of the search is coprime
+    let (mut offset, mut factor) = (0, 1 % m);
+    loop {
+        let g = ext_gcd(base, m).0;
+        if g == 1 {
+            break;
+        }
+        if target == factor {
+            return narrow(offset);
+        }
+        if target % g != 0 {
+            return None;
+        }
+        target /= g;
+        m /= g;
+        offset += 1;
+        factor = mul_mod(factor, base / g, m);
+    }
+
+    let n = (m as f64).sqrt().ceil() as i128 + 1;
+    let mut baby = HashMap::new();
+    let mut cur = target % m;
+    for q in 0..=n {
+        baby.insert(cur, q);
+        cur = mul_mod(cur, base % m, m);
+    }
+    let giant = pow_mod(base, n, m);
+    let mut cur = factor % m;
+    for p in 1..=n {
+        cur = mul_mod(cur, giant, m);
+        if let Some(q) = baby.get(&cur) {
+            return narrow(n * p - q + offset);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gcd_lcm() {
+        assert_eq!(ext_gcd(240, 46), (2, -9, 47));
+        assert_eq!(gcd(12u8, 18), Some(6));
+        assert_eq!(lcm(4usize, 6), Some(12));
+        assert_eq!(lcm(u64::MAX, 2), None);
+        assert_eq!(lcm(1u128 << 100, 3 << 99), Some(3 << 100));
+    }
+
+    #[test]
+    fn test_modular() {
+        assert_eq!(mod_pow(4u64, 13, 497), Some(445));
+        assert_eq!(mod_pow(7u64, 0, 1), Some(0));
+        assert_eq!(mod_pow(3i64, 200, (1 << 62) + 1), Some(3631617398189077956));
+        assert_eq!(mod_inv(3i32, 11), Some(4));
+        assert_eq!(mod_inv(-3i32, 11), Some(7));
+        assert_eq!(mod_inv(6u32, 9), None);
+    }
+
+    #[test]
+    fn test_crt() {
+        assert_eq!(crt(&[(2, 3), (3, 5), (2, 7)]), Some((23, 105)));
+        assert_eq!(crt(&[(3u64, 4), (5, 6)]), Some((11, 12)));
+        assert_eq!(crt(&[(1i64, 4), (2, 6)]), None);
+        assert_eq!(crt::<u8>(&[]), Some((0, 1)));
+        assert_eq!(crt(&[(0u8, 200), (1, 3)]), None);
+    }
+
+    #[test]
+    fn test_discrete_log() {
+        assert_eq!(discrete_log(7u64, 5764801, 20201227), Some(8));
+        assert_eq!(discrete_log(2, 1, 7), Some(0));
+        assert_eq!(discrete_log(2, 3, 7), None);
+        assert_eq!(discrete_log(2u32, 8, 24), Some(3));
+        assert_eq!(discrete_log(6u32, 0, 36), Some(2));
+    }
+}
@@ -0,0 +1,140 @@
+//! Splitting items into groups with the same total weight.
+//!
+//! `subsets_with_sum` walks a subset-sum table, so it only explores choices that can still reach
+//! the target. `best_group` picks the group that minimises a key (e.g. `(size, product)` to
+//! compare by size first) among those that leave a remainder that can be split too.
+
+/// Sums that the items from `i` onwards can make, at `reach[i][sum]`
+fn reachable(weights: &[u64], target: u64) -> Vec<Vec<bool>> {
+    let target = target as usize;
+    let mut reach = vec![vec![false; target + 1]; weights.len() + 1];
+    reach[weights.len()][0] = true;
+    for (i, &w) in weights.iter().enumerate().rev() {
+        for sum in 0..=target {
+            reach[i][sum] =
+                reach[i + 1][sum] || (sum >= w as usize && reach[i + 1][sum - w as usize]);
+        }
+    }
+    reach
+}
+
+fn collect(
+    weights: &[u64],
+    reach: &[Vec<bool>],
+    i: usize,
+    remaining: u64,
+    current: &mut Vec<usize>,
+    out: &mut Vec<Vec<usize>>,
+) {
+    if !reach[i][remaining as usize] {
+        return;
+    }
+    if remaining == 0 {
+        out.push(current.clone());
+        return;
+    }
+    if weights[i] <= remaining {
+        current.push(i);
+        collect(weights, reach, i + 1, remaining - weights[i], current, out);
+        current.pop();
+    }
+    collect(weights, reach, i + 1, remaining, current, out);
+}
+
+/// Indices of every subset of the items whose weights add up to `target`
+pub fn subsets_with_sum(weights: &[u64], target: u64) -> Vec<Vec<usize>> {
+    let reach = reachable(weights, target);
+    let mut out = vec![];
+    collect(weights, &reach, 0, target, &mut vec![], &mut out);
+    out
+}
+
+fn fill(weights: &[u64], bins: &mut [u64], target: u64) -> bool {
+    let Some((&w, rest)) = weights.split_first() else {
+        return true;
+    };
+    for b in 0..bins.len() {
+        // Bins with the same load are interchangeable
+        if bins[b] + w > target || bins[..b].contains(&bins[b]) {
+            continue;
+        }
+        bins[b] += w;
+        if fill(rest, bins, target) {
+            return true;
+        }
+        bins[b] -= w;
+    }
+    false
+}
+
+/// Whether the items can be split into `k` groups with the same total weight
+pub fn can_partition(weights: &[u64], k: usize) -> bool {
+    if k == 0 {
+        return weights.is_empty();
+    }
+    let total: u64 = weights.iter().sum();
+    if !total.is_multiple_of(k as u64) {
+        return false;
+    }
+    let target = total / k as u64;
+    if weights.iter().any(|&w| w > target) {
+        return false;
+    }
+    // Placing the heaviest items first fails faster
+    let mut sorted = weights.to_vec();
+    sorted.sort_unstable_by(|a, b| b.cmp(a));
+    fill(&sorted, &mut vec![0; k], target)
+}
+
+/// The group (as indices) with the lowest `key`, out of the groups that take a `k`-th of the
+/// total weight and leave items that can be split into `k - 1` equal groups
+pub fn best_group<K: Ord>(
+    weights: &[u64],
+    k: usize,
+    key: impl Fn(&[u64]) -> K,
+) -> Option<Vec<usize>> {
+    let total: u64 = weights.iter().sum();
+    if k == 0 || !total.is_multiple_of(k as u64) {
+        return None;
+    }
+    let mut groups = subsets_with_sum(weights, total / k as u64);
+    groups.sort_by_cached_key(|group| {
+        let group: Vec<u64> = group.iter().map(|&i| weights[i]).collect();
+        key(&group)
+    });
+    groups.into_iter().find(|group| {
+        let rest: Vec<u64> = (0..weights.len())
+            .filter(|i| !group.contains(i))
+            .map(|i| weights[i])
+            .collect();
+        can_partition(&rest, k - 1)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partition() {
+        let weights = [1, 2, 3, 4, 5, 7, 8, 9, 10, 11];
+        assert_eq!(subsets_with_sum(&[1, 2, 3, 4], 5), [vec![0, 3], vec![1, 2]]);
+        assert!(can_partition(&weights, 3));
+        assert!(can_partition(&weights, 4));
+        assert!(!can_partition(&weights, 6));
+        assert!(!can_partition(&[3, 3, 3, 1], 2));
+
+        let size_then_product = |g: &[u64]| (g.len(), g.iter().product::<u64>());
+        let group = best_group(&weights, 3, size_then_product).unwrap();
+        assert_eq!(group, [7, 9]);
+        let group = best_group(&weights, 4, size_then_product).unwrap();
+        let group: Vec<u64> = group.iter().map(|&i| weights[i]).collect();
+        assert_eq!(group, [4, 11]);
+        // Largest product first
+        let group = best_group(&weights, 3, |g| {
+            std::cmp::Reverse(g.iter().product::<u64>())
+        });
+        assert!(group.is_some());
+        assert_eq!(best_group(&[2, 2, 4], 3, size_then_product), None);
+    }
+}
@@ -0,0 +1,139 @@
+//! Simple polygons with vertices on the integer lattice.
+//!
+//! Coordinates are `i128`, so paths with huge steps (and their areas) do not overflow.
+use crate::numtheory::ext_gcd;
+
+pub type Point = [i128; 2];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Location {
+    Inside,
+    Boundary,
+    Outside,
+}
+
+/// A closed path, given by its vertices in order. The last vertex connects back to the first one.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Polygon {
+    vertices: Vec<Point>,
+}
+
+impl Polygon {
+    pub fn new(mut vertices: Vec<Point>) -> Self {
+        if vertices.len() > 1 && vertices.first() == vertices.last() {
+            vertices.pop();
+        }
+        Polygon { vertices }
+    }
+
+    /// Follow a list of `(direction, length)` steps from `start`, e.g. `([0, 1], 5)`.
+    /// The path should end back at `start`.
+    pub fn from_steps(start: Point, steps: impl IntoIterator<Item = (Point, i128)>) -> Self {
+        let mut pos = start;
+        let mut vertices = vec![start];
+        for (dir, len) in steps {
+            pos = [pos[0] + dir[0] * len, pos[1] + dir[1] * len];
+            vertices.push(pos);
+        }
+        Polygon::new(vertices)
+    }
+
+    pub fn vertices(&self) -> &[Point] {
+        &self.vertices
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (Point, Point)> + '_ {
+        self.vertices
+            .iter()
+            .copied()
+            .zip(self.vertices.iter().copied().cycle().skip(1))
+    }
+
+    /// Twice the area (shoelace formula), which is always an integer
+    pub fn double_area(&self) -> i128 {
+        self.edges()
+            .map(|(a, b)| a[0] * b[1] - b[0] * a[1])
+            .sum::<i128>()
+            .abs()
+    }
+
+    /// Number of lattice points on the boundary
+    pub fn boundary(&self) -> i128 {
+        self.edges()
+            .map(|(a, b)| ext_gcd(b[0] - a[0], b[1] - a[1]).0)
+            .sum()
+    }
+
+    /// Number of lattice points strictly inside, using Pick's theorem: `A = i + b/2 - 1`
+    pub fn interior(&self) -> i128 {
+        (self.double_area() - self.boundary() + 2) / 2
+    }
+
+    /// Number of lattice points inside or on the boundary
+    pub fn lattice_points(&self) -> i128 {
+        self.interior() + self.boundary()
+    }
+
+    pub fn locate(&self, p: Point) -> Location {
+        let mut inside = false;
+        for (a, b) in self.edges() {
+            let cross = (b[0] - a[0]) * (p[1] - a[1]) - (p[0] - a[0]) * (b[1] - a[1]);
+            if cross == 0
+                && a[0].min(b[0]) <= p[0]
+                && p[0] <= a[0].max(b[0])
+                && a[1].min(b[1]) <= p[1]
+                && p[1] <= a[1].max(b[1])
+            {
+                return Location::Boundary;
+            }
+            // Cast a ray in the positive x direction, and count the edges that cross it
+            if (a[1] > p[1]) != (b[1] > p[1]) && (cross > 0) == (b[1] > a[1]) {
+                inside = !inside;
+            }
+        }
+        if inside {
+            Location::Inside
+        } else {
+            Location::Outside
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_polygon() {
+        // 3x2 rectangle with a notch on the top side
+        let p = Polygon::from_steps(
+            [0, 0],
+            [
+                ([1, 0], 3),
+                ([0, 1], 2),
+                ([-1, 0], 1),
+                ([0, -1], 1),
+                ([-1, 0], 1),
+                ([0, 1], 1),
+                ([-1, 0], 1),
+                ([0, -1], 2),
+            ],
+        );
+        assert_eq!(p.vertices().len(), 8);
+        assert_eq!(p.double_area(), 10);
+        assert_eq!(p.boundary(), 12);
+        assert_eq!(p.interior(), 0);
+        assert_eq!(p.lattice_points(), 12);
+
+        let triangle = Polygon::new(vec![[0, 0], [4, 0], [0, 4], [0, 0]]);
+        assert_eq!(triangle.double_area(), 16);
+        assert_eq!(triangle.boundary(), 12);
+        assert_eq!(triangle.interior(), 3);
+        assert_eq!(triangle.locate([1, 1]), Location::Inside);
+        assert_eq!(triangle.locate([2, 2]), Location::Boundary);
+        assert_eq!(triangle.locate([0, 3]), Location::Boundary);
+        assert_eq!(triangle.locate([3, 3]), Location::Outside);
+        assert_eq!(triangle.locate([-1, 0]), Location::Outside);
+        assert_eq!(triangle.locate([5, 0]), Location::Outside);
+    }
+}
@@ -0,0 +1,214 @@
+//! Expression parser (Pratt, or "top down operator precedence") where the operators are
+//! declared by the caller, with a binding power and an associativity. Arithmetic with
+//! unusual rules, like addition before multiplication, only needs a different `Grammar`.
+use std::collections::HashMap;
+use std::fmt;
+use std::iter::Peekable;
+use std::vec::IntoIter;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Assoc {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Ast {
+    Num(i64),
+    Prefix(String, Box<Ast>),
+    Infix(String, Box<Ast>, Box<Ast>),
+}
+
+impl Ast {
+    /// Value with the usual meaning of `+`, `-`, `*`, `/`, `%`, `^` and prefix `-`
+    pub fn eval(&self) -> Result<i64, String> {
+        self.eval_with(&|op, args| match (op, args) {
+            ("-", [a]) => a.checked_neg(),
+            ("+", [a, b]) => a.checked_add(*b),
+            ("-", [a, b]) => a.checked_sub(*b),
+            ("*", [a, b]) => a.checked_mul(*b),
+            ("/", [a, b]) => a.checked_div(*b),
+            ("%", [a, b]) => a.checked_rem(*b),
+            ("^", [a, b]) if (0..=u32::MAX as i64).contains(b) => a.checked_pow(*b as u32),
+            _ => None,
+        })
+    }
+
+    /// Value using `apply(operator, operands)` for every operation, which returns `None` if it
+    /// cannot be computed
+    pub fn eval_with<F>(&self, apply: &F) -> Result<i64, String>
+    where
+        F: Fn(&str, &[i64]) -> Option<i64>,
+    {
+        let (op, args) = match self {
+            Ast::Num(n) => return Ok(*n),
+            Ast::Prefix(op, a) => (op, vec![a.eval_with(apply)?]),
+            Ast::Infix(op, a, b) => (op, vec![a.eval_with(apply)?, b.eval_with(apply)?]),
+        };
+        apply(op, &args).ok_or_else(|| format!("cannot compute {} on {:?}", op, args))
+    }
+}
+
+/// Every operation is wrapped in parentheses, which shows how the expression was grouped
+impl fmt::Display for Ast {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Ast::Num(n) => write!(f, "{}", n),
+            Ast::Prefix(op, a) => write!(f, "({}{})", op, a),
+            Ast::Infix(op, a, b) => write!(f, "({} {} {})", a, op, b),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Num(i64),
+    Op(String),
+    Open,
+    Close,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Grammar {
+    /// Binding power and associativity of each infix operator
+    infix: HashMap<String, (u8, Assoc)>,
+    /// Binding power of each prefix operator
+    prefix: HashMap<String, u8>,
+}
+
+impl Grammar {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Add an infix operator. Operators with more power are applied first.
+    pub fn infix(mut self, op: &str, power: u8, assoc: Assoc) -> Self {
+        self.infix.insert(op.to_string(), (power, assoc));
+        self
+    }
+
+    /// Add a prefix operator, which takes everything to its right that binds tighter than
+    /// `power`
+    pub fn prefix(mut self, op: &str, power: u8) -> Self {
+        self.prefix.insert(op.to_string(), power);
+        self
+    }
+
+    fn tokenize(&self, input: &str) -> Result<Vec<Token>, String> {
+        let mut tokens = vec![];
+        let mut rest = input.trim_start();
+        while let Some(c) = rest.chars().next() {
+            let len = if c.is_ascii_digit() {
+                let len = rest
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(rest.len());
+                let n = rest[..len].parse().map_err(|e| format!("{}", e))?;
+                tokens.push(Token::Num(n));
+                len
+            } else if c == '(' || c == ')' {
+                tokens.push(if c == '(' { Token::Open } else { Token::Close });
+                1
+            } else {
+                // The longest operator, so `**` is not read as two `*`
+                let op = self
+                    .infix
+                    .keys()
+                    .chain(self.prefix.keys())
+                    .filter(|op| rest.starts_with(op.as_str()))
+                    .max_by_key(|op| op.len())
+                    .ok_or_else(|| format!("unexpected input: {}", rest))?;
+                tokens.push(Token::Op(op.clone()));
+                op.len()
+            };
+            rest = rest[len..].trim_start();
+        }
+        Ok(tokens)
+    }
+
+    pub fn parse(&self, input: &str) -> Result<Ast, String> {
+        let mut tokens = self.tokenize(input)?.into_iter().peekable();
+        let ast = self.expr(&mut tokens, 0)?;
+        match tokens.next() {
+            None => Ok(ast),
+            Some(t) => Err(format!("unexpected {:?}", t)),
+        }
+    }
+
+    /// Parse operations until one binds less than `min_power`
+    fn expr(&self, tokens: &mut Peekable<IntoIter<Token>>, min_power: u8) -> Result<Ast, String> {
+        let mut lhs = match tokens.next() {
+            Some(Token::Num(n)) => Ast::Num(n),
+            Some(Token::Open) => {
+                let inner = self.expr(tokens, 0)?;
+                if tokens.next() != Some(Token::Close) {
+                    return Err("unclosed parenthesis".to_string());
+                }
+                inner
+            }
+            Some(Token::Op(op)) => {
+                let power = *self
+                    .prefix
+                    .get(&op)
+                    .ok_or_else(|| format!("{} is not a prefix operator", op))?;
+                Ast::Prefix(op, Box::new(self.expr(tokens, power)?))
+            }
+            t => return Err(format!("expected an operand, found {:?}", t)),
+        };
+        loop {
+            let op = match tokens.peek() {
+                Some(Token::Op(op)) => op.clone(),
+                Some(Token::Close) | None => break,
+                Some(t) => return Err(format!("expected an operator, found {:?}", t)),
+            };
+            let (power, assoc) = *self
+                .infix
+                .get(&op)
+                .ok_or_else(|| format!("{} is not an infix operator", op))?;
+            if power < min_power {
+                break;
+            }
+            tokens.next();
+            // The right side of a left associative operator stops at the next one of the same
+            // power, so that it is grouped with the left side instead
+            let next_power = match assoc {
+                Assoc::Left => power + 1,
+                Assoc::Right => power,
+            };
+            let rhs = self.expr(tokens, next_power)?;
+            lhs = Ast::Infix(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let arithmetic = Grammar::new()
+            .infix("+", 1, Assoc::Left)
+            .infix("-", 1, Assoc::Left)
+            .infix("*", 2, Assoc::Left)
+            .infix("/", 2, Assoc::Left)
+            .infix("^", 4, Assoc::Right)
+            .prefix("-", 3);
+        let ast = arithmetic.parse("1 - 2 - 3 * 4 ^ 3 ^ 0").unwrap();
+        assert_eq!(ast.to_string(), "((1 - 2) - (3 * (4 ^ (3 ^ 0))))");
+        assert_eq!(ast.eval(), Ok(-13));
+        let ast = arithmetic.parse("-2 ^ 2 * -(1 + 2)").unwrap();
+        assert_eq!(ast.to_string(), "((-(2 ^ 2)) * (-(1 + 2)))");
+        assert_eq!(ast.eval(), Ok(12));
+        assert!(arithmetic.parse("7 / 0").unwrap().eval().is_err());
+
+        for invalid in &["", "1 +", "(1 + 2", "1 + 2)", "1 2", "1 & 2", "* 3"] {
+            assert!(arithmetic.parse(invalid).is_err(), "{}", invalid);
+        }
+
+        let reversed = Grammar::new()
+            .infix("+", 2, Assoc::Left)
+            .infix("*", 1, Assoc::Left);
+        assert_eq!(reversed.parse("2 * 3 + 4").unwrap().eval(), Ok(14));
+    }
+}
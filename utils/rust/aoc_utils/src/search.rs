@@ -0,0 +1,375 @@
+//! State-space search for optimisation puzzles: find the best sequence of moves, where each
+//! move adds a value (e.g. pressure released, or mana spent).
+//!
+//! A puzzle implements `Problem` and picks an algorithm:
+//! * `branch_and_bound`: depth-first search that skips states whose `bound` cannot beat the
+//!   best solution so far, or that are dominated by a state seen before. Exact.
+//! * `beam_search`: breadth-first, keeping only the `width` most promising states of each
+//!   step. Fast, but it may miss the best solution.
+//! * `memoized_dfs`: computes the best value from every state once. Exact, for problems where
+//!   the key captures everything that matters and no state can be reached from itself.
+//!
+//! Every search also adds its `Stats` to a per-thread total, which the runners print after
+//! each part (see `take_stats`).
+pub use crate::tsp::Objective;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::ops::AddAssign;
+
+pub trait Problem {
+    type State: Clone;
+    type Key: Hash + Eq;
+
+    fn objective(&self) -> Objective;
+
+    /// States reachable in one move, with the value of that move
+    fn successors(&self, state: &Self::State) -> Vec<(Self::State, i64)>;
+
+    /// Whether the search can stop at this state
+    fn is_goal(&self, state: &Self::State) -> bool;
+
+    /// Identity of a state. States with the same key are compared with `dominates`, and
+    /// `memoized_dfs` assumes they have the same future.
+    fn key(&self, state: &Self::State) -> Self::Key;
+
+    /// Optimistic estimate of the value still to be gained from this state: an upper bound when
+    /// maximising, a lower bound when minimising. `None` if there is no bound.
+    fn bound(&self, _state: &Self::State) -> Option<i64> {
+        None
+    }
+
+    /// Whether `a` is at least as good as `b` (with the same key), given the value of the
+    /// moves that led to each of them, so `b` does not need to be explored
+    fn dominates(&self, a: (&Self::State, i64), b: (&Self::State, i64)) -> bool {
+        !better(self.objective(), b.1, Some(a.1))
+    }
+}
+
+fn better(objective: Objective, value: i64, than: Option<i64>) -> bool {
+    match (objective, than) {
+        (_, None) => true,
+        (Objective::Min, Some(than)) => value < than,
+        (Objective::Max, Some(than)) => value > than,
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// States whose successors were generated
+    pub expanded: usize,
+    pub generated: usize,
+    /// States skipped because their bound could not beat the best solution
+    pub pruned_bound: usize,
+    /// States skipped because a state with the same key was at least as good
+    pub pruned_dominated: usize,
+    /// States left out of the beam
+    pub pruned_beam: usize,
+    pub memo_hits: usize,
+}
+
+impl AddAssign for Stats {
+    fn add_assign(&mut self, other: Stats) {
+        self.expanded += other.expanded;
+        self.generated += other.generated;
+        self.pruned_bound += other.pruned_bound;
+        self.pruned_dominated += other.pruned_dominated;
+        self.pruned_beam += other.pruned_beam;
+        self.memo_hits += other.memo_hits;
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "expanded {}, generated {}, pruned {} by bound, {} dominated",
+            self.expanded, self.generated, self.pruned_bound, self.pruned_dominated
+        )?;
+        if self.pruned_beam > 0 {
+            write!(f, ", {} out of the beam", self.pruned_beam)?;
+        }
+        if self.memo_hits > 0 {
+            write!(f, ", {} memo hits", self.memo_hits)?;
+        }
+        Ok(())
+    }
+}
+
+thread_local! {
+    static TOTAL: RefCell<Option<Stats>> = const { RefCell::new(None) };
+}
+
+fn record(stats: Stats) {
+    TOTAL.with(|total| *total.borrow_mut().get_or_insert_with(Stats::default) += stats);
+}
+
+/// Statistics of every search run in this thread since the last call, if there was any
+pub fn take_stats() -> Option<Stats> {
+    TOTAL.with(|total| total.borrow_mut().take())
+}
+
+#[derive(Debug, Clone)]
+pub struct Outcome<S> {
+    /// Value of the best solution found
+    pub value: Option<i64>,
+    /// The goal state of that solution (not tracked by `memoized_dfs`)
+    pub state: Option<S>,
+    pub stats: Stats,
+}
+
+impl<S> Outcome<S> {
+    fn new(best: Option<(i64, S)>, stats: Stats) -> Self {
+        record(stats);
+        let (value, state) = match best {
+            Some((value, state)) => (Some(value), Some(state)),
+            None => (None, None),
+        };
+        Outcome {
+            value,
+            state,
+            stats,
+        }
+    }
+}
+
+/// States that are not dominated by any other state with the same key
+struct Frontier<'a, P: Problem> {
+    problem: &'a P,
+    seen: HashMap<P::Key, Vec<(P::State, i64)>>,
+}
+
+impl<'a, P: Problem> Frontier<'a, P> {
+    fn new(problem: &'a P) -> Self {
+        Frontier {
+            problem,
+            seen: HashMap::new(),
+        }
+    }
+
+    /// Add a state, unless a state seen before dominates it
+    fn insert(&mut self, state: &P::State, value: i64) -> bool {
+        let problem = self.problem;
+        let front = self.seen.entry(problem.key(state)).or_default();
+        if front
+            .iter()
+            .any(|(other, v)| problem.dominates((other, *v), (state, value)))
+        {
+            return false;
+        }
+        front.retain(|(other, v)| !problem.dominates((state, value), (other, *v)));
+        front.push((state.clone(), value));
+        true
+    }
+}
+
+/// Depth-first search that prunes states that cannot improve the best solution found so far
+pub fn branch_and_bound<P: Problem>(problem: &P, start: P::State) -> Outcome<P::State> {
+    let objective = problem.objective();
+    let mut stats = Stats {
+        generated: 1,
+        ..Default::default()
+    };
+    let mut best: Option<(i64, P::State)> = None;
+    let promising = |state: &P::State, value: i64, best: &Option<(i64, P::State)>| match (
+        problem.bound(state),
+        best,
+    ) {
+        (Some(bound), Some((best, _))) => better(objective, value + bound, Some(*best)),
+        _ => true,
+    };
+    let mut frontier = Frontier::new(problem);
+    frontier.insert(&start, 0);
+    let mut stack = vec![(start, 0)];
+    while let Some((state, value)) = stack.pop() {
+        // The best solution may have improved since the state was added
+        if !promising(&state, value, &best) {
+            stats.pruned_bound += 1;
+            continue;
+        }
+        if problem.is_goal(&state) && better(objective, value, best.as_ref().map(|b| b.0)) {
+            best = Some((value, state.clone()));
+        }
+        stats.expanded += 1;
+        for (next, step) in problem.successors(&state) {
+            stats.generated += 1;
+            let value = value + step;
+            if !promising(&next, value, &best) {
+                stats.pruned_bound += 1;
+            } else if !frontier.insert(&next, value) {
+                stats.pruned_dominated += 1;
+            } else {
+                stack.push((next, value));
+            }
+        }
+    }
+    Outcome::new(best, stats)
+}
+
+/// Breadth-first search that only keeps the `width` states with the best bound at every
+/// depth. It is not exact, but its cost does not explode.
+pub fn beam_search<P: Problem>(problem: &P, start: P::State, width: usize) -> Outcome<P::State> {
+    let objective = problem.objective();
+    let mut stats = Stats {
+        generated: 1,
+        ..Default::default()
+    };
+    let mut best: Option<(i64, P::State)> = None;
+    let mut beam = vec![(start, 0)];
+    while !beam.is_empty() {
+        let mut frontier = Frontier::new(problem);
+        let mut next_beam = vec![];
+        for (state, value) in beam {
+            if problem.is_goal(&state) && better(objective, value, best.as_ref().map(|b| b.0)) {
+                best = Some((value, state.clone()));
+            }
+            stats.expanded += 1;
+            for (next, step) in problem.successors(&state) {
+                stats.generated += 1;
+                if frontier.insert(&next, value + step) {
+                    next_beam.push((next, value + step));
+                } else {
+                    stats.pruned_dominated += 1;
+                }
+            }
+        }
+        // States without a bound go last
+        let estimate = |(state, value): &(P::State, i64)| {
+            problem.bound(state).map(|bound| match objective {
+                Objective::Max => -(value + bound),
+                Objective::Min => value + bound,
+            })
+        };
+        next_beam.sort_by_cached_key(|entry| (estimate(entry).is_none(), estimate(entry)));
+        if next_beam.len() > width {
+            stats.pruned_beam += next_beam.len() - width;
+            next_beam.truncate(width);
+        }
+        beam = next_beam;
+    }
+    Outcome::new(best, stats)
+}
+
+fn best_from<P: Problem>(
+    problem: &P,
+    state: &P::State,
+    memo: &mut HashMap<P::Key, Option<i64>>,
+    stats: &mut Stats,
+) -> Option<i64> {
+    let key = problem.key(state);
+    if let Some(value) = memo.get(&key) {
+        stats.memo_hits += 1;
+        return *value;
+    }
+    let objective = problem.objective();
+    let mut best = if problem.is_goal(state) {
+        Some(0)
+    } else {
+        None
+    };
+    stats.expanded += 1;
+    for (next, step) in problem.successors(state) {
+        stats.generated += 1;
+        if let Some(value) = best_from(problem, &next, memo, stats) {
+            if better(objective, step + value, best) {
+                best = Some(step + value);
+            }
+        }
+    }
+    memo.insert(key, best);
+    best
+}
+
+/// Best value of the moves from `start` to a goal, computing it only once for every key
+pub fn memoized_dfs<P: Problem>(problem: &P, start: P::State) -> Outcome<P::State> {
+    let mut stats = Stats {
+        generated: 1,
+        ..Default::default()
+    };
+    let value = best_from(problem, &start, &mut HashMap::new(), &mut stats);
+    record(stats);
+    Outcome {
+        value,
+        state: None,
+        stats,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Knapsack: pick items in order, without going over the capacity
+    struct Knapsack {
+        items: Vec<(i64, i64)>,
+        capacity: i64,
+    }
+
+    impl Problem for Knapsack {
+        /// Next item to consider, and the weight used
+        type State = (usize, i64);
+        type Key = (usize, i64);
+
+        fn objective(&self) -> Objective {
+            Objective::Max
+        }
+
+        fn successors(&self, &(next, weight): &Self::State) -> Vec<(Self::State, i64)> {
+            let Some(&(item_weight, item_value)) = self.items.get(next) else {
+                return vec![];
+            };
+            let mut options = vec![((next + 1, weight), 0)];
+            if weight + item_weight <= self.capacity {
+                options.push(((next + 1, weight + item_weight), item_value));
+            }
+            options
+        }
+
+        fn is_goal(&self, _state: &Self::State) -> bool {
+            true
+        }
+
+        fn key(&self, state: &Self::State) -> Self::Key {
+            *state
+        }
+
+        fn bound(&self, &(next, _): &Self::State) -> Option<i64> {
+            Some(
+                self.items[next.min(self.items.len())..]
+                    .iter()
+                    .map(|i| i.1)
+                    .sum(),
+            )
+        }
+    }
+
+    #[test]
+    fn test_search() {
+        let problem = Knapsack {
+            items: vec![(5, 10), (4, 40), (6, 30), (3, 50), (2, 5), (7, 25)],
+            capacity: 10,
+        };
+        take_stats();
+        let exact = branch_and_bound(&problem, (0, 0));
+        assert_eq!(exact.value, Some(95));
+        assert_eq!(exact.state.map(|(_, weight)| weight), Some(9));
+        assert!(exact.stats.pruned_bound > 0);
+
+        let memo = memoized_dfs(&problem, (0, 0));
+        assert_eq!(memo.value, Some(95));
+        assert!(memo.stats.memo_hits > 0);
+
+        let beam = beam_search(&problem, (0, 0), 4);
+        assert_eq!(beam.value, Some(95));
+        assert!(beam.stats.pruned_beam > 0);
+        assert_eq!(beam_search(&problem, (0, 0), 1).value, Some(50));
+
+        let total = take_stats().unwrap();
+        assert_eq!(
+            total.expanded,
+            exact.stats.expanded + memo.stats.expanded + beam.stats.expanded + 7
+        );
+        assert_eq!(take_stats(), None);
+    }
+}
@@ -0,0 +1,462 @@
+//! Symbolic expressions, for puzzles made of definitions like `root: pppw + sjmn` or
+//! `x AND y -> d`.
+//!
+//! Definitions refer to each other by name, so together they form a DAG. `Definitions` can
+//! evaluate it (computing every name only once), expand an expression into a tree to simplify
+//! and print it, and solve an equation for a name, as long as the equation is linear in it.
+//! Solving uses exact rationals, so intermediate divisions do not need to be exact.
+use crate::linalg::{rational, to_integers, Rational};
+use num_traits::{One, Zero};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    And,
+    Or,
+    Shl,
+    Shr,
+    /// Bitwise not, the only unary operation
+    Not,
+}
+
+impl Op {
+    fn symbol(self) -> &'static str {
+        match self {
+            Op::Add => "+",
+            Op::Sub => "-",
+            Op::Mul => "*",
+            Op::Div => "/",
+            Op::And => "AND",
+            Op::Or => "OR",
+            Op::Shl => "LSHIFT",
+            Op::Shr => "RSHIFT",
+            Op::Not => "NOT",
+        }
+    }
+
+    fn precedence(self) -> u8 {
+        match self {
+            Op::Or => 1,
+            Op::And => 2,
+            Op::Shl | Op::Shr => 3,
+            Op::Add | Op::Sub => 4,
+            Op::Mul | Op::Div => 5,
+            Op::Not => 6,
+        }
+    }
+
+    /// Result of the operation on integers. Unary operations ignore `b`.
+    pub fn apply(self, a: i128, b: i128) -> Result<i128, String> {
+        let res = match self {
+            Op::Add => a.checked_add(b),
+            Op::Sub => a.checked_sub(b),
+            Op::Mul => a.checked_mul(b),
+            Op::Div => a.checked_div(b),
+            Op::And => Some(a & b),
+            Op::Or => Some(a | b),
+            Op::Shl => u32::try_from(b).ok().and_then(|b| a.checked_shl(b)),
+            Op::Shr => u32::try_from(b).ok().and_then(|b| a.checked_shr(b)),
+            Op::Not => Some(!a),
+        };
+        res.ok_or_else(|| format!("cannot compute {} {} {}", a, self, b))
+    }
+}
+
+impl FromStr for Op {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [
+            Op::Add,
+            Op::Sub,
+            Op::Mul,
+            Op::Div,
+            Op::And,
+            Op::Or,
+            Op::Shl,
+            Op::Shr,
+            Op::Not,
+        ]
+        .into_iter()
+        .find(|op| op.symbol() == s)
+        .ok_or_else(|| format!("unknown operation {}", s))
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Expr {
+    Num(i128),
+    /// Another definition, or an unknown if there is no definition with that name
+    Var(String),
+    Unary(Op, Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn atom(s: &str) -> Expr {
+        s.parse()
+            .map_or_else(|_| Expr::Var(s.to_string()), Expr::Num)
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Unary(op, _) | Expr::Binary(op, _, _) => op.precedence(),
+            _ => u8::MAX,
+        }
+    }
+
+    /// Fold operations on numbers, and remove the ones that do nothing (e.g. `x + 0` or `x * 1`)
+    pub fn simplify(self) -> Expr {
+        match self {
+            Expr::Unary(op, a) => match a.simplify() {
+                Expr::Num(n) if op.apply(n, 0).is_ok() => Expr::Num(op.apply(n, 0).unwrap()),
+                a => Expr::Unary(op, Box::new(a)),
+            },
+            Expr::Binary(op, a, b) => {
+                let (a, b) = (a.simplify(), b.simplify());
+                match (op, &a, &b) {
+                    (_, Expr::Num(x), Expr::Num(y)) if op.apply(*x, *y).is_ok() => {
+                        return Expr::Num(op.apply(*x, *y).unwrap())
+                    }
+                    (Op::Mul | Op::And, Expr::Num(0), _) | (Op::Mul | Op::And, _, Expr::Num(0)) => {
+                        return Expr::Num(0)
+                    }
+                    (Op::Add | Op::Sub | Op::Or | Op::Shl | Op::Shr, _, Expr::Num(0))
+                    | (Op::Mul | Op::Div, _, Expr::Num(1)) => return a,
+                    (Op::Add | Op::Or, Expr::Num(0), _) | (Op::Mul, Expr::Num(1), _) => return b,
+                    _ => {}
+                }
+                Expr::Binary(op, Box::new(a), Box::new(b))
+            }
+            e => e,
+        }
+    }
+}
+
+/// Parse a single operation: `x`, `42`, `NOT x`, or `x + y`
+impl FromStr for Expr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens: Vec<&str> = s.split_whitespace().collect();
+        match tokens[..] {
+            [atom] => Ok(Expr::atom(atom)),
+            [op, a] => Ok(Expr::Unary(op.parse()?, Box::new(Expr::atom(a)))),
+            [a, op, b] => Ok(Expr::Binary(
+                op.parse()?,
+                Box::new(Expr::atom(a)),
+                Box::new(Expr::atom(b)),
+            )),
+            _ => Err(format!("invalid expression {}", s)),
+        }
+    }
+}
+
+fn write_operand(f: &mut fmt::Formatter, e: &Expr, precedence: u8) -> fmt::Result {
+    if e.precedence() < precedence {
+        write!(f, "({})", e)
+    } else {
+        write!(f, "{}", e)
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Num(n) => write!(f, "{}", n),
+            Expr::Var(name) => write!(f, "{}", name),
+            Expr::Unary(op, a) => {
+                write!(f, "{} ", op)?;
+                write_operand(f, a, op.precedence())
+            }
+            Expr::Binary(op, a, b) => {
+                write_operand(f, a, op.precedence())?;
+                write!(f, " {} ", op)?;
+                // a - (b + c) needs the parentheses, (a + b) - c does not
+                write_operand(f, b, op.precedence() + 1)
+            }
+        }
+    }
+}
+
+/// `coef * x + constant`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Linear {
+    pub coef: Rational,
+    pub constant: Rational,
+}
+
+impl Linear {
+    fn constant(constant: Rational) -> Self {
+        Linear {
+            coef: Rational::zero(),
+            constant,
+        }
+    }
+
+    fn integer(&self) -> Option<i128> {
+        if self.coef.is_zero() {
+            to_integers(std::slice::from_ref(&self.constant)).map(|v| v[0])
+        } else {
+            None
+        }
+    }
+
+    fn scale(self, factor: &Rational) -> Self {
+        Linear {
+            coef: self.coef * factor,
+            constant: self.constant * factor,
+        }
+    }
+}
+
+/// Named expressions, which should not depend on themselves
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Definitions {
+    exprs: HashMap<String, Expr>,
+    mask: Option<i128>,
+}
+
+impl Definitions {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Keep only the bits in `mask` after every evaluated operation, e.g. `0xffff` for 16 bit
+    /// signals
+    pub fn with_mask(mask: i128) -> Self {
+        Definitions {
+            mask: Some(mask),
+            ..Default::default()
+        }
+    }
+
+    /// Define (or redefine) a name, returning its previous definition
+    pub fn define(&mut self, name: impl Into<String>, expr: Expr) -> Option<Expr> {
+        self.exprs.insert(name.into(), expr)
+    }
+
+    pub fn undefine(&mut self, name: &str) -> Option<Expr> {
+        self.exprs.remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Expr> {
+        self.exprs.get(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.exprs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.exprs.is_empty()
+    }
+
+    /// Value of a name. Fails if it depends on an undefined name or an operation overflows.
+    pub fn eval(&self, name: &str) -> Result<i128, String> {
+        self.eval_expr(&Expr::Var(name.to_string()))
+    }
+
+    pub fn eval_expr(&self, expr: &Expr) -> Result<i128, String> {
+        self.eval_memo(expr, &mut HashMap::new())
+    }
+
+    fn eval_memo<'a>(
+        &'a self,
+        expr: &'a Expr,
+        memo: &mut HashMap<&'a str, i128>,
+    ) -> Result<i128, String> {
+        let value = match expr {
+            Expr::Num(n) => *n,
+            Expr::Var(name) => {
+                if let Some(value) = memo.get(name.as_str()) {
+                    return Ok(*value);
+                }
+                let def = self
+                    .get(name)
+                    .ok_or_else(|| format!("{} is not defined", name))?;
+                let value = self.eval_memo(def, memo)?;
+                memo.insert(name, value);
+                return Ok(value);
+            }
+            Expr::Unary(op, a) => op.apply(self.eval_memo(a, memo)?, 0)?,
+            Expr::Binary(op, a, b) => {
+                op.apply(self.eval_memo(a, memo)?, self.eval_memo(b, memo)?)?
+            }
+        };
+        Ok(self.mask.map_or(value, |mask| value & mask))
+    }
+
+    /// The expression with every defined name replaced by its definition, so only the
+    /// unknowns are left. Shared names are copied, so the tree can be much bigger than the DAG.
+    pub fn expand(&self, expr: &Expr) -> Expr {
+        match expr {
+            Expr::Var(name) => match self.get(name) {
+                Some(def) => self.expand(def),
+                None => expr.clone(),
+            },
+            Expr::Unary(op, a) => Expr::Unary(*op, Box::new(self.expand(a))),
+            Expr::Binary(op, a, b) => {
+                Expr::Binary(*op, Box::new(self.expand(a)), Box::new(self.expand(b)))
+            }
+            Expr::Num(_) => expr.clone(),
+        }
+    }
+
+    /// The expression as a linear function of `unknown`, ignoring its definition if it has one
+    pub fn linear(&self, expr: &Expr, unknown: &str) -> Result<Linear, String> {
+        self.linear_memo(expr, unknown, &mut HashMap::new())
+    }
+
+    fn linear_memo<'a>(
+        &'a self,
+        expr: &'a Expr,
+        unknown: &str,
+        memo: &mut HashMap<&'a str, Linear>,
+    ) -> Result<Linear, String> {
+        let not_linear = |op: &Op| format!("{} is not linear in {}", op, unknown);
+        let res = match expr {
+            Expr::Num(n) => Linear::constant(rational(*n)),
+            Expr::Var(name) if name == unknown => Linear {
+                coef: Rational::one(),
+                constant: Rational::zero(),
+            },
+            Expr::Var(name) => {
+                if let Some(value) = memo.get(name.as_str()) {
+                    return Ok(value.clone());
+                }
+                let def = self
+                    .get(name)
+                    .ok_or_else(|| format!("{} is not defined", name))?;
+                let value = self.linear_memo(def, unknown, memo)?;
+                memo.insert(name, value.clone());
+                value
+            }
+            Expr::Unary(op, a) => {
+                let a = self.linear_memo(a, unknown, memo)?;
+                let a = a.integer().ok_or_else(|| not_linear(op))?;
+                Linear::constant(rational(op.apply(a, 0)?))
+            }
+            Expr::Binary(op, a, b) => {
+                let a = self.linear_memo(a, unknown, memo)?;
+                let b = self.linear_memo(b, unknown, memo)?;
+                match op {
+                    Op::Add => Linear {
+                        coef: a.coef + b.coef,
+                        constant: a.constant + b.constant,
+                    },
+                    Op::Sub => Linear {
+                        coef: a.coef - b.coef,
+                        constant: a.constant - b.constant,
+                    },
+                    Op::Mul if a.coef.is_zero() => b.scale(&a.constant),
+                    Op::Mul if b.coef.is_zero() => a.scale(&b.constant),
+                    Op::Div if b.coef.is_zero() && !b.constant.is_zero() => {
+                        a.scale(&b.constant.recip())
+                    }
+                    _ => match (a.integer(), b.integer()) {
+                        (Some(a), Some(b)) => Linear::constant(rational(op.apply(a, b)?)),
+                        _ => return Err(not_linear(op)),
+                    },
+                }
+            }
+        };
+        Ok(res)
+    }
+
+    /// Value of `unknown` that makes both expressions equal, if they are linear in it
+    pub fn solve(&self, lhs: &Expr, rhs: &Expr, unknown: &str) -> Result<Rational, String> {
+        let mut memo = HashMap::new();
+        let lhs = self.linear_memo(lhs, unknown, &mut memo)?;
+        let rhs = self.linear_memo(rhs, unknown, &mut memo)?;
+        let coef = lhs.coef - rhs.coef;
+        if coef.is_zero() {
+            return Err(format!("the equation does not depend on {}", unknown));
+        }
+        Ok((rhs.constant - lhs.constant) / coef)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn definitions(lines: &[(&str, &str)]) -> Definitions {
+        let mut defs = Definitions::new();
+        for (name, expr) in lines {
+            defs.define(*name, expr.parse().unwrap());
+        }
+        defs
+    }
+
+    #[test]
+    fn test_solve() {
+        let defs = definitions(&[
+            ("root", "a + b"),
+            ("a", "x * 4"),
+            ("b", "c - x"),
+            ("c", "d / 3"),
+            ("d", "12"),
+            ("x", "5"),
+        ]);
+        assert_eq!(defs.eval("root"), Ok(19));
+        let Some(Expr::Binary(_, a, b)) = defs.get("root") else {
+            unreachable!()
+        };
+        // x * 4 == 4 - x
+        assert_eq!(defs.solve(a, b, "x"), Ok(Rational::new(4.into(), 5.into())));
+        assert!(defs.solve(a, a, "x").is_err());
+        assert!(defs.solve(&"x * x".parse().unwrap(), b, "x").is_err());
+
+        let mut unknown = defs.clone();
+        unknown.undefine("x");
+        assert!(unknown.eval("root").is_err());
+        let tree = unknown.expand(&Expr::Var("root".into()));
+        assert_eq!(tree.to_string(), "x * 4 + (12 / 3 - x)");
+        assert_eq!(tree.simplify().to_string(), "x * 4 + (4 - x)");
+        assert_eq!(
+            Expr::Binary(
+                Op::Mul,
+                Box::new("x + 0".parse().unwrap()),
+                Box::new("1 * 1".parse().unwrap())
+            )
+            .simplify(),
+            Expr::Var("x".into())
+        );
+    }
+
+    #[test]
+    fn test_mask() {
+        let mut defs = Definitions::with_mask(0xffff);
+        for line in [
+            "123 -> x",
+            "456 -> y",
+            "x AND y -> d",
+            "x LSHIFT 2 -> f",
+            "NOT x -> h",
+        ] {
+            let (expr, name) = line.split_once(" -> ").unwrap();
+            defs.define(name, expr.parse().unwrap());
+        }
+        assert_eq!(defs.eval("d"), Ok(72));
+        assert_eq!(defs.eval("f"), Ok(492));
+        assert_eq!(defs.eval("h"), Ok(65412));
+        assert_eq!(
+            "NOT h".parse(),
+            Ok(Expr::Unary(Op::Not, Box::new(Expr::Var("h".into()))))
+        );
+        assert!("x + y + z".parse::<Expr>().is_err());
+    }
+}
@@ -0,0 +1,231 @@
+//! Travelling salesman on small graphs, solved exactly with the Held-Karp dynamic programming
+//! over subsets: the best way to visit every node in a set and finish at a given node.
+//!
+//! It takes `O(2^n * n^2)` time and `O(2^n * n)` memory, which is fine for the ~10 nodes of most
+//! puzzles, and still usable for ~20.
+use crate::graph::Interner;
+use std::hash::Hash;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Objective {
+    Min,
+    Max,
+}
+
+impl Objective {
+    fn better(self, cost: i64, than: Option<i64>) -> bool {
+        match (self, than) {
+            (_, None) => true,
+            (Objective::Min, Some(than)) => cost < than,
+            (Objective::Max, Some(than)) => cost > than,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shape {
+    /// Visit every node once, and finish anywhere
+    Path,
+    /// Visit every node once, and go back to the first one
+    Cycle,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    pub cost: i64,
+    /// Nodes in the order they are visited. Cycles do not repeat the first node at the end.
+    pub nodes: Vec<usize>,
+}
+
+/// Costs of going from one node to another. Missing edges cannot be used.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Tsp {
+    costs: Vec<Vec<Option<i64>>>,
+}
+
+impl Tsp {
+    pub fn new(n: usize) -> Self {
+        Tsp {
+            costs: vec![vec![None; n]; n],
+        }
+    }
+
+    /// Graph with an undirected edge for every `(a, b, cost)`, and the ids of the labels
+    pub fn from_labelled_edges<T: Hash + Eq + Clone>(
+        edges: impl IntoIterator<Item = (T, T, i64)>,
+    ) -> (Self, Interner<T>) {
+        let mut labels = Interner::new();
+        let mut tsp = Tsp::default();
+        for (a, b, cost) in edges {
+            let (a, b) = (labels.intern(a), labels.intern(b));
+            tsp.add_edge(a, b, cost);
+        }
+        (tsp, labels)
+    }
+
+    fn grow(&mut self, n: usize) {
+        if self.costs.len() < n {
+            for row in self.costs.iter_mut() {
+                row.resize(n, None);
+            }
+            self.costs.resize(n, vec![None; n]);
+        }
+    }
+
+    /// Set the cost of going from `from` to `to`, adding any node that did not exist yet
+    pub fn add_arc(&mut self, from: usize, to: usize, cost: i64) {
+        self.grow(from.max(to) + 1);
+        self.costs[from][to] = Some(cost);
+    }
+
+    /// Set the cost between `a` and `b` in both directions
+    pub fn add_edge(&mut self, a: usize, b: usize, cost: i64) {
+        self.add_arc(a, b, cost);
+        self.add_arc(b, a, cost);
+    }
+
+    pub fn cost(&self, from: usize, to: usize) -> Option<i64> {
+        self.costs[from][to]
+    }
+
+    pub fn len(&self) -> usize {
+        self.costs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.costs.is_empty()
+    }
+
+    /// Best route through every node, starting at `start` or at any node. Cycles start at node
+    /// 0 if no start is given, since every node is part of them. Returns `None` if the edges
+    /// do not allow any route.
+    pub fn solve(&self, objective: Objective, shape: Shape, start: Option<usize>) -> Option<Route> {
+        let n = self.len();
+        assert!(n <= 24, "too many nodes for Held-Karp: {}", n);
+        let start = match shape {
+            Shape::Cycle => Some(start.unwrap_or(0)),
+            Shape::Path => start,
+        };
+        if n == 0 || start.is_some_and(|s| s >= n) {
+            return None;
+        }
+        if n == 1 {
+            return Some(Route {
+                cost: 0,
+                nodes: vec![0],
+            });
+        }
+        // Best cost and previous node of the routes that visit the nodes in `mask` and finish
+        // at `last`, at `mask * n + last`
+        let full = 1usize << n;
+        let mut best: Vec<Option<i64>> = vec![None; full * n];
+        let mut previous: Vec<usize> = vec![usize::MAX; full * n];
+        for first in 0..n {
+            if start.is_none_or(|s| s == first) {
+                best[(1 << first) * n + first] = Some(0);
+            }
+        }
+        for mask in 1..full {
+            for last in 0..n {
+                let cost = match best[mask * n + last] {
+                    Some(cost) => cost,
+                    None => continue,
+                };
+                for next in (0..n).filter(|next| mask & (1 << next) == 0) {
+                    if let Some(step) = self.costs[last][next] {
+                        let ix = (mask | 1 << next) * n + next;
+                        if objective.better(cost + step, best[ix]) {
+                            best[ix] = Some(cost + step);
+                            previous[ix] = last;
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut end: Option<(usize, i64)> = None;
+        for last in 0..n {
+            let cost = match (shape, best[(full - 1) * n + last]) {
+                (_, None) => continue,
+                (Shape::Path, Some(cost)) => cost,
+                (Shape::Cycle, Some(cost)) => match self.costs[last][start.unwrap()] {
+                    Some(back) => cost + back,
+                    None => continue,
+                },
+            };
+            if objective.better(cost, end.map(|(_, c)| c)) {
+                end = Some((last, cost));
+            }
+        }
+        let (last, cost) = end?;
+
+        let mut nodes = vec![last];
+        let (mut mask, mut node) = (full - 1, last);
+        while mask.count_ones() > 1 {
+            let prev = previous[mask * n + node];
+            mask ^= 1 << node;
+            node = prev;
+            nodes.push(node);
+        }
+        nodes.reverse();
+        Some(Route { cost, nodes })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tsp() {
+        let (tsp, labels) = Tsp::from_labelled_edges([
+            ("London", "Dublin", 464),
+            ("London", "Belfast", 518),
+            ("Dublin", "Belfast", 141),
+        ]);
+        let shortest = tsp.solve(Objective::Min, Shape::Path, None).unwrap();
+        assert_eq!(shortest.cost, 605);
+        let names: Vec<&str> = shortest.nodes.iter().map(|&id| *labels.label(id)).collect();
+        assert!(
+            names == ["London", "Dublin", "Belfast"] || names == ["Belfast", "Dublin", "London"]
+        );
+        assert_eq!(
+            tsp.solve(Objective::Max, Shape::Path, None).unwrap().cost,
+            982
+        );
+        let from_dublin = tsp.solve(Objective::Min, Shape::Path, Some(1)).unwrap();
+        assert_eq!((from_dublin.cost, from_dublin.nodes[0]), (659, 1));
+        assert_eq!(
+            tsp.solve(Objective::Min, Shape::Cycle, None).unwrap().cost,
+            1123
+        );
+
+        // A directed cycle that can only be walked one way
+        let mut tsp = Tsp::new(4);
+        for (from, to) in [(0, 2), (2, 1), (1, 3), (3, 0)] {
+            tsp.add_arc(from, to, 1);
+        }
+        tsp.add_arc(0, 1, 10);
+        let cycle = tsp.solve(Objective::Min, Shape::Cycle, Some(1)).unwrap();
+        assert_eq!(
+            cycle,
+            Route {
+                cost: 4,
+                nodes: vec![1, 3, 0, 2]
+            }
+        );
+        assert_eq!(
+            tsp.solve(Objective::Max, Shape::Cycle, None).unwrap().cost,
+            4
+        );
+        assert_eq!(
+            tsp.solve(Objective::Max, Shape::Path, Some(0))
+                .unwrap()
+                .cost,
+            3
+        );
+        tsp.add_arc(3, 0, 5);
+        tsp.grow(5);
+        assert_eq!(tsp.solve(Objective::Min, Shape::Path, None), None);
+    }
+}